@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// ASCII output of iteration counts, one character per sample.
+
+use crate::color::Colors;
+use crate::mapping::Intensity;
+
+const SHORT: &str = "@%#*+=~:. ";
+// Paul Bourke's 70 level ramp
+const LONG: &str = "$@B%8&WM#*oahkbdpqwmZO0QLCJUYXzcvunxrjft/\\|()1{}[]?-_+~<>i!lI;:,\"^`'. ";
+const BLOCKS: &str = "█▓▒░ ";
+const DIGITS: &str = "0123456789";
+
+// characters to draw with, from densest to blank
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ramp {
+    chars: Vec<char>,
+}
+
+impl Default for Ramp {
+    fn default() -> Self {
+        Self::new(SHORT).expect("built-in ramp")
+    }
+}
+
+impl Ramp {
+    pub const NAMES: [&'static str; 4] = ["short", "long", "blocks", "digits"];
+
+    // a ramp from any string of at least two characters
+    pub fn new(chars: &str) -> Option<Self> {
+        let chars: Vec<char> = chars.chars().collect();
+        match chars.len() {
+            0 | 1 => None,
+            _ => Some(Self { chars }),
+        }
+    }
+
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "short" => Self::new(SHORT),
+            "long" => Self::new(LONG),
+            "blocks" => Self::new(BLOCKS),
+            "digits" => Self::new(DIGITS),
+            _ => None,
+        }
+    }
+
+    // the same characters the other way round, for dark text on a light
+    // background
+    pub fn inverted(mut self) -> Self {
+        self.chars.reverse();
+        self
+    }
+
+    // character for a value, in equal bands from the first at 0 to the last
+    // at 255
+    pub fn value_char(&self, value: u8) -> char {
+        self.chars[value as usize * self.chars.len() / 256]
+    }
+
+    // character for a sample: the set and the slowest escaping points take
+    // the first, fading out to the last for the fastest
+    pub fn shade_char(&self, intensity: Intensity) -> char {
+        let t = intensity.unwrap_or(1.0);
+        let i = ((1.0 - t) * self.chars.len() as f64) as usize;
+        self.chars[i.min(self.chars.len() - 1)]
+    }
+}
+
+// changes an intensity into a character from the default ramp, which is
+// all ASCII so can be indexed by byte
+pub fn val_to_char(value: u8) -> char {
+    SHORT.as_bytes()[value as usize * SHORT.len() / 256] as char
+}
+
+// one line of characters per grid row, coloured if enabled. escape codes are
+// only emitted when the colour actually changes
+pub fn render_ascii(grid: &[Intensity], cols: usize, colors: &Colors, ramp: &Ramp) -> Vec<String> {
+    grid.chunks(cols)
+        .map(|row| {
+            let mut line = String::with_capacity(cols);
+            let mut last = None;
+            for &intensity in row {
+                let rgb = colors.intensity_rgb(intensity);
+                if rgb != last {
+                    colors.set_fg(&mut line, rgb);
+                    last = rgb;
+                }
+                line.push(ramp.shade_char(intensity));
+            }
+            if last.is_some() {
+                colors.reset(&mut line);
+            }
+            line
+        })
+        .collect()
+}
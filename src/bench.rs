@@ -0,0 +1,73 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// FPU throughput benchmark: the default viewport rendered a fixed number of
+// times, so the figures can be compared between boards.
+
+use crate::fractal::{render, Ifs, Iter, MAX_ITER, VIEW_MAX, VIEW_MIN};
+use crate::precision::Precision;
+use std::time::{Duration, Instant};
+
+// the fixed workload
+pub const COLS: usize = 256;
+pub const ROWS: usize = 128;
+pub const ROUNDS: u32 = 5;
+
+pub struct Bench {
+    pub precision: Precision,
+    pub threads: usize,
+    pub rounds: u32,
+    pub pixels: u64,
+    // total steps of the iterated function, over all rounds
+    pub iterations: u64,
+    pub elapsed: Duration,
+}
+
+impl Bench {
+    pub fn run(precision: Precision, threads: usize) -> Self {
+        let mandel = Ifs::new(MAX_ITER);
+        let start = Instant::now();
+        let mut iterations = 0;
+        for _ in 0..ROUNDS {
+            let grid = render(precision, &mandel, VIEW_MIN, VIEW_MAX, COLS, ROWS, threads);
+            iterations += steps(&grid, MAX_ITER);
+        }
+
+        Self {
+            precision,
+            threads,
+            rounds: ROUNDS,
+            pixels: (COLS * ROWS) as u64 * ROUNDS as u64,
+            iterations,
+            elapsed: start.elapsed(),
+        }
+    }
+
+    pub fn iterations_per_sec(&self) -> f64 {
+        self.iterations as f64 / self.elapsed.as_secs_f64()
+    }
+
+    // wall time per pixel, so with more than one thread this is the
+    // throughput rather than the time any one pixel took
+    pub fn ns_per_pixel(&self) -> f64 {
+        self.elapsed.as_nanos() as f64 / self.pixels as f64
+    }
+}
+
+// iterations taken over a grid of values from Ifs::iter, where points inside
+// the set ran to the limit
+pub fn steps(grid: &[Iter], max_iter: Iter) -> u64 {
+    grid.iter()
+        .map(|&value| {
+            let steps = match value {
+                0 => max_iter,
+                value => max_iter - value,
+            };
+            // Iter is already u64 with the u64 feature
+            #[allow(clippy::unnecessary_cast)]
+            let steps = steps as u64;
+            steps
+        })
+        .sum()
+}
@@ -0,0 +1,404 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Minimal command-line parsing. We don't pull in an argument parsing crate
+// to keep the binary small on flash-constrained targets.
+
+use float_test::{julia_preset, ColorMode, Formula, ImageFormat, Iter, Palette, Precision};
+use float_test::{newton, Mapping, Newton, Ramp, Renderer, Shortcuts, Viewport, MAX_ITER};
+use num::complex::Complex;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread;
+
+// output format for results
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Report {
+    Text,
+    Json,
+}
+
+// parsed command line
+#[derive(Clone, Debug)]
+pub struct Args {
+    pub selftest: bool,
+    pub dump_grid: bool,
+    pub compare: bool,
+    pub per_core: bool,
+    pub bench: bool,
+    pub explore: bool,
+    pub precisions: Vec<Precision>,
+    pub viewport: Viewport,
+    pub formula: Formula,
+    pub julia: Option<Complex<f64>>,
+    pub newton: Option<Vec<f64>>,
+    pub max_iter: Iter,
+    pub smooth: bool,
+    pub subdivide: bool,
+    pub shortcuts: Shortcuts,
+    pub mapping: Mapping,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub no_clamp: bool,
+    pub color: Option<ColorMode>,
+    pub palette: Palette,
+    pub renderer: Renderer,
+    pub ramp: Ramp,
+    pub output: Option<(PathBuf, ImageFormat)>,
+    pub threads: usize,
+    pub report: Report,
+    pub help: bool,
+}
+
+// things that can go wrong while parsing
+#[derive(Debug)]
+pub enum ArgError {
+    Unknown(String),
+    MissingValue(&'static str),
+    BadValue(&'static str, String),
+    Conflict(&'static str, &'static str),
+    Requires(&'static str, &'static str),
+    EmptyViewport,
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgError::Unknown(arg) => write!(f, "unknown argument '{}'", arg),
+            ArgError::MissingValue(flag) => write!(f, "{} requires a value", flag),
+            ArgError::BadValue(flag, value) => write!(f, "invalid value '{}' for {}", value, flag),
+            ArgError::Conflict(a, b) => write!(f, "{} can't be used with {}", a, b),
+            ArgError::Requires(a, b) => write!(f, "{} requires {}", a, b),
+            ArgError::EmptyViewport => {
+                write!(
+                    f,
+                    "the viewport must be finite, with --max above --min in both parts"
+                )
+            }
+        }
+    }
+}
+
+pub const USAGE: &str = "\
+usage: float_test [options]
+
+options:
+  --selftest        run the IEEE-754 conformance checks and compare the
+                    reference grid instead of rendering
+  --compare         render in single and double precision and map where
+                    the results differ
+  --per-core        render the reference grid on every CPU at once, each
+                    worker pinned to its own, and report any that disagree
+                    (Linux only)
+  --bench           time a fixed render in both precisions and report
+                    iterations per second, with the checks if combined with
+                    --selftest or --report json
+  --explore         browse the set in the terminal: arrow keys pan, +/- zoom,
+                    [ and ] halve and double the iteration limit, f cycles
+                    through the formulas and q quits. click to center on a
+                    point, scroll to zoom around it or drag out a rectangle
+                    to zoom into
+  --dump-grid       print the reference grid as rendered on this machine
+  --precision <p>   float precision: single, double or both (--selftest only),
+                    defaults to the build's native precision
+  --min <re,im>     lowest corner of the viewport (with --max)
+  --max <re,im>     highest corner of the viewport (with --min)
+  --center <re,im>  center of the viewport, instead of --min/--max
+  --zoom <z>        magnification around --center (default 1)
+  --formula <f>     mandelbrot (default), burning-ship, tricorn or
+                    multibrot:<power>, with a power above 1
+  --julia <c>       render the julia set for the constant c, given as re,im
+                    or one of rabbit, dendrite, siegel, san-marco, airplane
+                    or spiral
+  --newton <a,b,..> render the newton fractal for the polynomial with these
+                    real coefficients, highest power first (e.g. 1,0,0,-1
+                    for z^3-1)
+  --max-iter <n>    iteration limit (default 256)
+  --smooth          colour by fractional iteration count, escaping at a
+                    radius of 256 rather than 2, to avoid banding
+  --subdivide       fill in rectangles whose border is all inside the set
+                    instead of iterating every point, which is much faster
+                    but can miss the odd pixel right on the boundary;
+                    mandelbrot only
+  --shortcuts <s>   skip iterating points known to be inside the set:
+                    bulbs (test for the main cardioid and period-2 bulb,
+                    mandelbrot only), periodicity (stop when the orbit
+                    repeats exactly), all or none (default), comma-separated
+  --mapping <m>     how escape counts map to characters and colours: linear,
+                    log (default), histogram or modulo[:<period>]
+  --width <cols>    output width, instead of the terminal width
+  --height <rows>   output height, instead of the terminal height
+  --no-clamp        don't limit the terminal size to 80-128 columns and
+                    40-128 rows
+  --color <mode>    auto (default), none, 16, 256 or truecolor
+  --palette <name>  grey, fire (default), ocean or rainbow
+  --renderer <r>    ascii (default), halfblock (2 samples per character) or
+                    braille (8 samples per character)
+  --ramp <r>        characters for ascii output, from densest to blank: short
+                    (default), long, blocks, digits or the characters
+                    themselves, e.g. --ramp \"#+. \"
+  --invert          reverse the ramp, for light-background terminals
+  -o, --output <f>  write an image (.png, .ppm or .pgm) instead of text, at
+                    --width x --height pixels (default 512x512)
+  --threads <n>     render with this many threads (default: one per CPU),
+                    which gives the same result as a single thread
+  --report <fmt>    result format: text (default) or json
+  -h, --help        show this message";
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            selftest: false,
+            dump_grid: false,
+            compare: false,
+            per_core: false,
+            bench: false,
+            explore: false,
+            precisions: vec![Precision::DEFAULT],
+            viewport: Viewport::default(),
+            formula: Formula::Mandelbrot,
+            julia: None,
+            newton: None,
+            max_iter: MAX_ITER,
+            smooth: false,
+            subdivide: false,
+            shortcuts: Shortcuts::default(),
+            mapping: Mapping::Log,
+            width: None,
+            height: None,
+            no_clamp: false,
+            color: None,
+            palette: Palette::Fire,
+            renderer: Renderer::Ascii,
+            ramp: Ramp::default(),
+            output: None,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            report: Report::Text,
+            help: false,
+        }
+    }
+}
+
+impl Args {
+    // parse arguments, not including the program name
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, ArgError> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        let (mut min, mut max, mut center, mut zoom) = (None, None, None, None);
+        let mut invert = false;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--min" => min = Some(complex_value("--min", args.next())?),
+                "--max" => max = Some(complex_value("--max", args.next())?),
+                "--center" => center = Some(complex_value("--center", args.next())?),
+                "--zoom" => zoom = Some(positive_real("--zoom", args.next())?),
+                "--formula" => {
+                    let value = args.next().ok_or(ArgError::MissingValue("--formula"))?;
+                    parsed.formula =
+                        Formula::from_name(&value).ok_or(ArgError::BadValue("--formula", value))?;
+                }
+                "--julia" => {
+                    let value = args.next().ok_or(ArgError::MissingValue("--julia"))?;
+                    parsed.julia = match julia_preset(&value) {
+                        Some(c) => Some(c),
+                        None => Some(complex_value("--julia", Some(value))?),
+                    };
+                }
+                "--newton" => {
+                    let value = args.next().ok_or(ArgError::MissingValue("--newton"))?;
+                    let coeffs: Option<Vec<f64>> =
+                        value.split(',').map(|a| a.trim().parse().ok()).collect();
+                    parsed.newton = match coeffs {
+                        Some(coeffs) if Newton::new(MAX_ITER, &coeffs).is_some() => Some(coeffs),
+                        _ => return Err(ArgError::BadValue("--newton", value)),
+                    };
+                }
+                "--max-iter" => parsed.max_iter = positive_value("--max-iter", args.next())?,
+                "--width" => parsed.width = Some(positive_value("--width", args.next())?),
+                "--height" => parsed.height = Some(positive_value("--height", args.next())?),
+                "--no-clamp" => parsed.no_clamp = true,
+                "--smooth" => parsed.smooth = true,
+                "--subdivide" => parsed.subdivide = true,
+                "--shortcuts" => {
+                    let value = args.next().ok_or(ArgError::MissingValue("--shortcuts"))?;
+                    parsed.shortcuts = Shortcuts::from_names(&value)
+                        .ok_or(ArgError::BadValue("--shortcuts", value))?;
+                }
+                "--mapping" => {
+                    let value = args.next().ok_or(ArgError::MissingValue("--mapping"))?;
+                    parsed.mapping =
+                        Mapping::from_name(&value).ok_or(ArgError::BadValue("--mapping", value))?;
+                }
+                "--color" => {
+                    let value = args.next().ok_or(ArgError::MissingValue("--color"))?;
+                    parsed.color = match (value.as_str(), ColorMode::from_name(&value)) {
+                        ("auto", _) => None,
+                        (_, Some(mode)) => Some(mode),
+                        _ => return Err(ArgError::BadValue("--color", value)),
+                    };
+                }
+                "--renderer" => {
+                    let value = args.next().ok_or(ArgError::MissingValue("--renderer"))?;
+                    parsed.renderer = Renderer::from_name(&value)
+                        .ok_or(ArgError::BadValue("--renderer", value))?;
+                }
+                "--ramp" => {
+                    let value = args.next().ok_or(ArgError::MissingValue("--ramp"))?;
+                    parsed.ramp = Ramp::named(&value)
+                        .or_else(|| Ramp::new(&value))
+                        .ok_or(ArgError::BadValue("--ramp", value))?;
+                }
+                "--invert" => invert = true,
+                "--output" | "-o" => {
+                    let value = args.next().ok_or(ArgError::MissingValue("--output"))?;
+                    let path = PathBuf::from(&value);
+                    let format = ImageFormat::from_path(&path)
+                        .ok_or(ArgError::BadValue("--output", value))?;
+                    parsed.output = Some((path, format));
+                }
+                "--palette" => {
+                    let value = args.next().ok_or(ArgError::MissingValue("--palette"))?;
+                    parsed.palette =
+                        Palette::from_name(&value).ok_or(ArgError::BadValue("--palette", value))?;
+                }
+                "--threads" => parsed.threads = positive_value("--threads", args.next())?,
+                "--selftest" => parsed.selftest = true,
+                "--dump-grid" => parsed.dump_grid = true,
+                "--compare" => parsed.compare = true,
+                "--per-core" => parsed.per_core = true,
+                "--bench" => parsed.bench = true,
+                "--explore" => parsed.explore = true,
+                "--precision" => {
+                    let value = args.next().ok_or(ArgError::MissingValue("--precision"))?;
+                    parsed.precisions = match Precision::from_name(&value) {
+                        Some(precision) => vec![precision],
+                        None if value == "both" => Precision::ALL.to_vec(),
+                        None => return Err(ArgError::BadValue("--precision", value)),
+                    };
+                }
+                "--report" => {
+                    let value = args.next().ok_or(ArgError::MissingValue("--report"))?;
+                    parsed.report = match value.as_str() {
+                        "text" => Report::Text,
+                        "json" => Report::Json,
+                        _ => return Err(ArgError::BadValue("--report", value)),
+                    };
+                }
+                "-h" | "--help" => parsed.help = true,
+                _ => return Err(ArgError::Unknown(arg)),
+            }
+        }
+
+        if parsed.per_core && parsed.report == Report::Json {
+            return Err(ArgError::Conflict("--per-core", "--report json"));
+        }
+        if parsed.per_core && parsed.bench {
+            return Err(ArgError::Conflict("--per-core", "--bench"));
+        }
+        if parsed.explore && parsed.output.is_some() {
+            return Err(ArgError::Conflict("--explore", "--output"));
+        }
+        if parsed.explore && parsed.newton.is_some() {
+            return Err(ArgError::Conflict("--explore", "--newton"));
+        }
+
+        // filling in relies on the set being connected, which only the
+        // mandelbrot set is sure to be
+        if parsed.subdivide && (parsed.julia.is_some() || parsed.formula != Formula::Mandelbrot) {
+            return Err(ArgError::Conflict("--subdivide", "--julia/--formula"));
+        }
+
+        if invert {
+            parsed.ramp = parsed.ramp.inverted();
+        }
+
+        if parsed.newton.is_some() {
+            if parsed.julia.is_some() || parsed.formula != Formula::Mandelbrot {
+                return Err(ArgError::Conflict("--newton", "--julia/--formula"));
+            }
+            if parsed.compare {
+                return Err(ArgError::Conflict("--newton", "--compare"));
+            }
+            if parsed.smooth {
+                return Err(ArgError::Conflict("--newton", "--smooth"));
+            }
+            if parsed.shortcuts.any() {
+                return Err(ArgError::Conflict("--newton", "--shortcuts"));
+            }
+            if parsed.subdivide {
+                return Err(ArgError::Conflict("--newton", "--subdivide"));
+            }
+            if parsed.renderer != Renderer::Ascii {
+                return Err(ArgError::Conflict("--newton", "--renderer"));
+            }
+        }
+
+        // julia sets sit around the origin, so they get their own default
+        let default = match (&parsed.newton, parsed.julia) {
+            (Some(_), _) => newton::VIEW,
+            (None, Some(_)) => Viewport::JULIA,
+            (None, None) => parsed.formula.viewport(),
+        };
+        parsed.viewport = match (min, max) {
+            (Some(min), Some(max)) => {
+                if center.is_some() || zoom.is_some() {
+                    return Err(ArgError::Conflict("--center/--zoom", "--min/--max"));
+                }
+                Viewport { min, max }
+            }
+            (Some(_), None) => return Err(ArgError::Requires("--min", "--max")),
+            (None, Some(_)) => return Err(ArgError::Requires("--max", "--min")),
+            (None, None) => match (center, zoom) {
+                (None, None) => default,
+                (center, zoom) => {
+                    default.zoomed(center.unwrap_or(default.center()), zoom.unwrap_or(1.0))
+                }
+            },
+        };
+        // a zoom can also take the corners out of range, or too close
+        // together to tell apart
+        let span = parsed.viewport.max - parsed.viewport.min;
+        if !(span.re.is_finite() && span.im.is_finite() && span.re > 0.0 && span.im > 0.0) {
+            return Err(ArgError::EmptyViewport);
+        }
+        Ok(parsed)
+    }
+}
+
+// parse a number that must be greater than zero
+fn positive_value<T>(flag: &'static str, value: Option<String>) -> Result<T, ArgError>
+where
+    T: FromStr + PartialOrd + Default,
+{
+    let value = value.ok_or(ArgError::MissingValue(flag))?;
+    match value.parse() {
+        Ok(parsed) if parsed > T::default() => Ok(parsed),
+        _ => Err(ArgError::BadValue(flag, value)),
+    }
+}
+
+// parse a finite real number that must be greater than zero
+fn positive_real(flag: &'static str, value: Option<String>) -> Result<f64, ArgError> {
+    let value = value.ok_or(ArgError::MissingValue(flag))?;
+    match value.parse::<f64>() {
+        Ok(parsed) if parsed.is_finite() && parsed > 0.0 => Ok(parsed),
+        _ => Err(ArgError::BadValue(flag, value)),
+    }
+}
+
+// parse a complex number written as "re,im"
+fn complex_value(flag: &'static str, value: Option<String>) -> Result<Complex<f64>, ArgError> {
+    let value = value.ok_or(ArgError::MissingValue(flag))?;
+    let parsed = value.split_once(',').and_then(|(re, im)| {
+        let re: f64 = re.trim().parse().ok()?;
+        let im: f64 = im.trim().parse().ok()?;
+        Some(Complex::new(re, im))
+    });
+    match parsed {
+        Some(c) if c.re.is_finite() && c.im.is_finite() => Ok(c),
+        _ => Err(ArgError::BadValue(flag, value)),
+    }
+}
@@ -0,0 +1,261 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// ANSI colour output: palettes, quantisation to what the terminal supports,
+// and detection of that from the environment.
+
+use crate::mapping::Intensity;
+use crossterm::style::{Color, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::Command;
+use std::env;
+use std::fmt::Write;
+use std::io::{self, IsTerminal};
+
+pub type Rgb = (u8, u8, u8);
+
+// how many colours the output can use
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    Plain,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" | "plain" => Some(ColorMode::Plain),
+            "16" => Some(ColorMode::Ansi16),
+            "256" => Some(ColorMode::Ansi256),
+            "truecolor" | "24bit" => Some(ColorMode::TrueColor),
+            _ => None,
+        }
+    }
+
+    // guess from the environment, falling back to plain ASCII when stdout
+    // isn't a terminal or the terminal is dumb (e.g. a serial console)
+    pub fn detect() -> Self {
+        if env::var_os("NO_COLOR").is_some() || !io::stdout().is_terminal() {
+            return ColorMode::Plain;
+        }
+        let term = env::var("TERM").unwrap_or_default();
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+
+        if term.is_empty() || term == "dumb" {
+            ColorMode::Plain
+        } else if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorMode::TrueColor
+        } else if term.contains("256color") {
+            ColorMode::Ansi256
+        } else {
+            ColorMode::Ansi16
+        }
+    }
+
+    // nearest colour this mode can show, None for plain and 16-colour
+    // output. crossterm writes even the basic colours as 256-colour escapes,
+    // which older consoles don't understand, so those are handled separately
+    fn color(self, rgb: Rgb) -> Option<Color> {
+        match self {
+            ColorMode::Plain | ColorMode::Ansi16 => None,
+            ColorMode::Ansi256 => Some(Color::AnsiValue(nearest_256(rgb))),
+            ColorMode::TrueColor => Some(Color::Rgb {
+                r: rgb.0,
+                g: rgb.1,
+                b: rgb.2,
+            }),
+        }
+    }
+}
+
+// the basic colours in SGR order, with xterm's default values
+const ANSI_16: [Rgb; 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+// levels of each channel in the 256-colour 6x6x6 cube
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance(a: Rgb, b: Rgb) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+// index of the nearest basic colour
+pub fn nearest_16(rgb: Rgb) -> u8 {
+    (0..ANSI_16.len())
+        .min_by_key(|&i| distance(rgb, ANSI_16[i]))
+        .unwrap_or(0) as u8
+}
+
+// index into the 256-colour palette, from the colour cube or the grey ramp
+pub fn nearest_256(rgb: Rgb) -> u8 {
+    let level = |x: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - x as i32).abs())
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+    let cube_index = 16 + 36 * r as u8 + 6 * g as u8 + b as u8;
+
+    // grey ramp runs from 8 to 238 in steps of 10
+    let mean = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let grey_step = (mean.saturating_sub(3) / 10).min(23) as u8;
+    let grey = 8 + 10 * grey_step;
+
+    if distance(rgb, (grey, grey, grey)) < distance(rgb, cube) {
+        232 + grey_step
+    } else {
+        cube_index
+    }
+}
+
+// colour gradients for escaping points
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Palette {
+    Grey,
+    Fire,
+    Ocean,
+    Rainbow,
+}
+
+impl Palette {
+    pub const ALL: [Self; 4] = [
+        Palette::Grey,
+        Palette::Fire,
+        Palette::Ocean,
+        Palette::Rainbow,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Grey => "grey",
+            Palette::Fire => "fire",
+            Palette::Ocean => "ocean",
+            Palette::Rainbow => "rainbow",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|p| p.name() == name)
+    }
+
+    // evenly spaced gradient stops
+    fn stops(self) -> &'static [Rgb] {
+        match self {
+            Palette::Grey => &[(32, 32, 32), (255, 255, 255)],
+            Palette::Fire => &[
+                (32, 0, 0),
+                (192, 0, 0),
+                (255, 128, 0),
+                (255, 255, 0),
+                (255, 255, 255),
+            ],
+            Palette::Ocean => &[(0, 0, 64), (0, 64, 255), (0, 255, 255), (255, 255, 255)],
+            Palette::Rainbow => &[
+                (255, 0, 0),
+                (255, 255, 0),
+                (0, 255, 0),
+                (0, 255, 255),
+                (0, 0, 255),
+                (255, 0, 255),
+            ],
+        }
+    }
+
+    // colour at position t in 0..=1 along the gradient
+    pub fn rgb(self, t: f64) -> Rgb {
+        let stops = self.stops();
+        let pos = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+        let i = (pos as usize).min(stops.len() - 2);
+        let frac = pos - i as f64;
+        let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * frac).round() as u8;
+        let (a, b) = (stops[i], stops[i + 1]);
+
+        (lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2))
+    }
+}
+
+// colour output settings
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Colors {
+    pub mode: ColorMode,
+    pub palette: Palette,
+}
+
+impl Colors {
+    pub const PLAIN: Self = Self {
+        mode: ColorMode::Plain,
+        palette: Palette::Fire,
+    };
+
+    // colour for an intensity, brighter the closer the point is to the
+    // boundary. points inside the set keep the terminal's own colour so they
+    // stay visible on both dark and light backgrounds
+    pub fn intensity_rgb(&self, intensity: Intensity) -> Option<Rgb> {
+        intensity.map(|t| self.palette.rgb(t))
+    }
+
+    // append the escape sequence to switch the foreground colour, or back to
+    // the default for None
+    pub fn set_fg(&self, out: &mut String, rgb: Option<Rgb>) {
+        match rgb {
+            Some(rgb) => self.set(out, rgb, false),
+            None => self.reset(out),
+        }
+    }
+
+    // append the escape sequence to switch the background colour
+    pub fn set_bg(&self, out: &mut String, rgb: Rgb) {
+        self.set(out, rgb, true)
+    }
+
+    fn set(&self, out: &mut String, rgb: Rgb, background: bool) {
+        match self.mode {
+            ColorMode::Plain => (),
+            ColorMode::Ansi16 => {
+                let index = nearest_16(rgb);
+                let base = if background { 40 } else { 30 };
+                let code = if index < 8 {
+                    base + index
+                } else {
+                    base + 60 + index - 8
+                };
+                let _ = write!(out, "\x1b[{}m", code);
+            }
+            _ => {
+                if let Some(color) = self.mode.color(rgb) {
+                    let _ = match background {
+                        true => SetBackgroundColor(color).write_ansi(out),
+                        false => SetForegroundColor(color).write_ansi(out),
+                    };
+                }
+            }
+        }
+    }
+
+    // append the escape sequence to go back to the default colours
+    pub fn reset(&self, out: &mut String) {
+        if self.mode != ColorMode::Plain {
+            let _ = ResetColor.write_ansi(out);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Per-core consistency check: one worker pinned to each CPU we may run on,
+// all rendering the reference viewport at once. A faulty core shows up as a
+// grid hash that disagrees with the rest. Pinning is only supported on Linux.
+
+use crate::precision::Precision;
+use crate::reference;
+use std::collections::HashMap;
+use std::io;
+use std::thread;
+
+// the reference grid hash from one CPU, or why it couldn't be pinned there
+pub struct CoreRun {
+    pub cpu: usize,
+    pub hash: io::Result<u64>,
+}
+
+#[cfg(target_os = "linux")]
+mod affinity {
+    use nix::sched::{sched_getaffinity, sched_setaffinity, CpuSet};
+    use nix::unistd::Pid;
+    use std::io;
+
+    // pid 0 means the calling thread
+    pub fn cpus() -> io::Result<Vec<usize>> {
+        let set = sched_getaffinity(Pid::from_raw(0))?;
+        Ok((0..CpuSet::count())
+            .filter(|&cpu| set.is_set(cpu).unwrap_or(false))
+            .collect())
+    }
+
+    pub fn pin(cpu: usize) -> io::Result<()> {
+        let mut set = CpuSet::new();
+        set.set(cpu)?;
+        Ok(sched_setaffinity(Pid::from_raw(0), &set)?)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod affinity {
+    use std::io;
+
+    pub fn cpus() -> io::Result<Vec<usize>> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn pin(_cpu: usize) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+// the CPUs this process is allowed to run on
+pub fn cpus() -> io::Result<Vec<usize>> {
+    affinity::cpus()
+}
+
+// render the reference grid on every CPU at once
+pub fn check(precision: Precision) -> io::Result<Vec<CoreRun>> {
+    let cpus = cpus()?;
+    thread::scope(|scope| {
+        let workers: Vec<_> = cpus
+            .iter()
+            .map(|&cpu| {
+                scope.spawn(move || CoreRun {
+                    cpu,
+                    hash: affinity::pin(cpu)
+                        .map(|()| reference::hash(&reference::render(precision))),
+                })
+            })
+            .collect();
+        Ok(workers
+            .into_iter()
+            .map(|worker| worker.join().expect("per-core worker panicked"))
+            .collect())
+    })
+}
+
+// the hash most cores agree on. a tie goes to the one matching the embedded
+// reference, if either does, so a single bad core out of two is still named
+pub fn consensus(runs: &[CoreRun], precision: Precision) -> Option<u64> {
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+    for run in runs {
+        if let Ok(hash) = run.hash {
+            *counts.entry(hash).or_default() += 1;
+        }
+    }
+    let expected = reference::expected_hash(precision);
+    counts
+        .into_iter()
+        .max_by_key(|&(hash, count)| (count, hash == expected, hash))
+        .map(|(hash, _)| hash)
+}
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Side-by-side single vs. double precision render of the same viewport.
+// Some disagreement near the boundary of the set is expected from the lower
+// precision alone; a broken FPU tends to show up as large or widespread
+// differences instead.
+
+use crate::ascii::val_to_char;
+use crate::fractal::{render, Ifs, Iter};
+use crate::precision::Precision;
+use num::complex::Complex;
+
+// per-cell iteration count differences, single minus double
+pub struct Divergence {
+    pub cols: usize,
+    pub deltas: Vec<i64>,
+}
+
+// number of log2-sized buckets for the diff map
+pub const BUCKETS: usize = 9;
+
+// bucket for a nonzero difference: 1, 2-3, 4-7, ... 256+
+pub fn bucket(delta: i64) -> usize {
+    (delta.unsigned_abs().ilog2() as usize).min(BUCKETS - 1)
+}
+
+// map character for a difference, blank where both precisions agree
+pub fn diff_char(delta: i64) -> char {
+    match delta {
+        0 => val_to_char(255),
+        _ => val_to_char(224 - 25 * bucket(delta) as u8),
+    }
+}
+
+impl Divergence {
+    pub fn new(
+        mandel: &Ifs,
+        min: Complex<f64>,
+        max: Complex<f64>,
+        cols: usize,
+        rows: usize,
+        threads: usize,
+    ) -> Self {
+        let single = render(Precision::Single, mandel, min, max, cols, rows, threads);
+        let double = render(Precision::Double, mandel, min, max, cols, rows, threads);
+        let deltas = single
+            .iter()
+            .zip(&double)
+            .map(|(&s, &d): (&Iter, &Iter)| s as i64 - d as i64)
+            .collect();
+
+        Self { cols, deltas }
+    }
+
+    pub fn differing(&self) -> usize {
+        self.deltas.iter().filter(|&&d| d != 0).count()
+    }
+
+    pub fn fraction(&self) -> f64 {
+        self.differing() as f64 / self.deltas.len() as f64
+    }
+
+    pub fn max_delta(&self) -> u64 {
+        self.deltas
+            .iter()
+            .map(|d| d.unsigned_abs())
+            .max()
+            .unwrap_or(0)
+    }
+
+    // mean absolute difference over the cells that differ
+    pub fn mean_delta(&self) -> f64 {
+        match self.differing() {
+            0 => 0.0,
+            n => self.deltas.iter().map(|d| d.unsigned_abs()).sum::<u64>() as f64 / n as f64,
+        }
+    }
+
+    // number of differing cells in each bucket
+    pub fn histogram(&self) -> [usize; BUCKETS] {
+        let mut histogram = [0; BUCKETS];
+        for &delta in self.deltas.iter().filter(|&&d| d != 0) {
+            histogram[bucket(delta)] += 1;
+        }
+        histogram
+    }
+
+    // ASCII diff map, one line per row
+    pub fn map(&self) -> Vec<String> {
+        self.deltas
+            .chunks(self.cols)
+            .map(|line| line.iter().map(|&d| diff_char(d)).collect())
+            .collect()
+    }
+}
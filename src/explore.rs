@@ -0,0 +1,379 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Interactive explorer: the set drawn full-screen on the alternate screen,
+// redrawn whenever the viewport, iteration limit or formula changes, or the
+// terminal is resized. each view is rendered coarse to fine on a thread of
+// its own, and abandoned as soon as the next one is asked for, so the
+// explorer keeps up with input however slow a full render is.
+
+use crate::cli::Args;
+use crossterm::event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode};
+use crossterm::event::{KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue};
+use float_test::{
+    sample_point, ColorMode, Colors, Escape, Formula, Ifs, Iter, Precision, Viewport,
+};
+use num::complex::Complex;
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// fraction of the view each arrow key moves it by
+const PAN_STEP: f64 = 0.125;
+
+// magnification for each press of + or -
+const ZOOM_STEP: f64 = 1.5;
+
+// [ and ] halve and double the iteration limit, but not past these. a limit
+// given on the command line outside them is left alone
+const EXPLORE_MIN_ITER: Iter = 16;
+const EXPLORE_MAX_ITER: Iter = 1 << 20;
+
+// the first pass takes one sample for each block of this many samples across
+// and down, and each pass after that halves the blocks, to the full grid
+const PASSES: [usize; 4] = [8, 4, 2, 1];
+
+// how often to check for finished passes while waiting for input
+const POLL: Duration = Duration::from_millis(20);
+
+const HELP: &str =
+    "arrows pan, +/- zoom, [/] iterations, f formula, q quit, click to center, scroll to zoom, drag to zoom in";
+
+// raw mode and the alternate screen, undone however the explorer exits
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let screen = Screen;
+        execute!(
+            io::stdout(),
+            EnterAlternateScreen,
+            EnableMouseCapture,
+            cursor::Hide
+        )?;
+        Ok(screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(
+            io::stdout(),
+            cursor::Show,
+            DisableMouseCapture,
+            LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// what a key press or mouse event does to the explorer
+enum Action {
+    Quit,
+    Redraw,
+    // show the rectangle being dragged out
+    Select,
+    Ignore,
+}
+
+// the terminal cells given over to the set, all but the status line
+fn grid_size() -> io::Result<(u16, u16)> {
+    let (width, height) = terminal::size()?;
+    Ok((width, height.saturating_sub(1)))
+}
+
+// the point at the top left of a cell, as rendered
+fn cell_point(view: Viewport, size: (u16, u16), col: u16, row: u16) -> Complex<f64> {
+    let (cols, rows) = (size.0 as usize, size.1 as usize);
+    sample_point(view.min, view.max, cols, rows, col as usize, row as usize)
+}
+
+// a mouse button pressed on the set, and where the pointer is now
+#[derive(Clone, Copy)]
+struct Drag {
+    from: (u16, u16),
+    to: (u16, u16),
+}
+
+impl Drag {
+    // top left and bottom right cells, inclusive
+    fn corners(self) -> ((u16, u16), (u16, u16)) {
+        let (from, to) = (self.from, self.to);
+        (
+            (from.0.min(to.0), from.1.min(to.1)),
+            (from.0.max(to.0), from.1.max(to.1)),
+        )
+    }
+}
+
+fn mouse(args: &mut Args, drag: &mut Option<Drag>, event: MouseEvent) -> io::Result<Action> {
+    let size = grid_size()?;
+    let (col, row) = (event.column, event.row.min(size.1.saturating_sub(1)));
+    let view = args.viewport;
+    match event.kind {
+        MouseEventKind::Down(MouseButton::Left) if event.row < size.1 => {
+            *drag = Some(Drag {
+                from: (col, row),
+                to: (col, row),
+            });
+            Ok(Action::Ignore)
+        }
+        MouseEventKind::Drag(MouseButton::Left) => match drag {
+            Some(drag) => {
+                drag.to = (col, row);
+                Ok(Action::Select)
+            }
+            None => Ok(Action::Ignore),
+        },
+        MouseEventKind::Up(MouseButton::Left) => {
+            let drag = match drag.take() {
+                Some(drag) => drag,
+                None => return Ok(Action::Ignore),
+            };
+            if drag.from == drag.to {
+                let center = cell_point(view, size, col, row);
+                args.viewport = view.zoomed(center, 1.0);
+            } else {
+                // out to the far side of the last cell, so a drag along a
+                // single row or column still covers some area
+                let ((c0, r0), (c1, r1)) = drag.corners();
+                args.viewport = Viewport {
+                    min: cell_point(view, size, c0, r0),
+                    max: cell_point(view, size, c1 + 1, r1 + 1),
+                };
+            }
+            Ok(Action::Redraw)
+        }
+        MouseEventKind::ScrollUp | MouseEventKind::ScrollDown if event.row < size.1 => {
+            let zoom = match event.kind {
+                MouseEventKind::ScrollUp => ZOOM_STEP,
+                _ => 1.0 / ZOOM_STEP,
+            };
+            args.viewport = view.zoomed_about(cell_point(view, size, col, row), zoom);
+            Ok(Action::Redraw)
+        }
+        _ => Ok(Action::Ignore),
+    }
+}
+
+// outline the rectangle being dragged out over the last frame
+fn select(out: &mut impl Write, lines: &[String], drag: Drag) -> io::Result<()> {
+    for (row, line) in lines.iter().enumerate() {
+        queue!(out, cursor::MoveTo(0, row as u16), Print(line))?;
+    }
+    let ((c0, r0), (c1, r1)) = drag.corners();
+    let across = "-".repeat((c1 - c0 + 1) as usize);
+    queue!(out, SetAttribute(Attribute::Reverse))?;
+    for row in [r0, r1] {
+        queue!(out, cursor::MoveTo(c0, row), Print(&across))?;
+    }
+    for row in r0 + 1..r1 {
+        for col in [c0, c1] {
+            queue!(out, cursor::MoveTo(col, row), Print('|'))?;
+        }
+    }
+    queue!(out, SetAttribute(Attribute::Reset))?;
+    out.flush()
+}
+
+fn key(args: &mut Args, key: KeyEvent) -> Action {
+    let view = args.viewport;
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Action::Quit,
+        KeyCode::Left => args.viewport = view.panned(-PAN_STEP, 0.0),
+        KeyCode::Right => args.viewport = view.panned(PAN_STEP, 0.0),
+        // the first row is the lowest imaginary part
+        KeyCode::Up => args.viewport = view.panned(0.0, -PAN_STEP),
+        KeyCode::Down => args.viewport = view.panned(0.0, PAN_STEP),
+        KeyCode::Char('+') | KeyCode::Char('=') => {
+            args.viewport = view.zoomed(view.center(), ZOOM_STEP)
+        }
+        KeyCode::Char('-') => args.viewport = view.zoomed(view.center(), 1.0 / ZOOM_STEP),
+        KeyCode::Char('[') if args.max_iter > EXPLORE_MIN_ITER => {
+            args.max_iter = (args.max_iter / 2).max(EXPLORE_MIN_ITER)
+        }
+        KeyCode::Char(']') if args.max_iter < EXPLORE_MAX_ITER => {
+            args.max_iter = (args.max_iter * 2).min(EXPLORE_MAX_ITER)
+        }
+        KeyCode::Char('f') => {
+            let next = Formula::ALL
+                .iter()
+                .position(|&f| f == args.formula)
+                .map_or(0, |i| (i + 1) % Formula::ALL.len());
+            args.formula = Formula::ALL[next];
+            if args.julia.is_none() {
+                args.viewport = args.formula.viewport();
+            }
+        }
+        _ => return Action::Ignore,
+    }
+    Action::Redraw
+}
+
+// formula, viewport and timing, with a reminder of the keys
+fn status(args: &Args, pass: &Pass) -> String {
+    let name = match args.julia {
+        Some(c) => format!("{} julia {}{:+}i", args.formula, c.re, c.im),
+        None => args.formula.to_string(),
+    };
+    let view = args.viewport;
+    let center = view.center();
+    let time = match pass.scale {
+        1 => format!("{} ms", pass.elapsed.as_millis()),
+        scale => format!("{} ms at 1/{}", pass.elapsed.as_millis(), scale),
+    };
+    format!(
+        " {} | {:.6}{:+.6}i width {:.3e} | max_iter {} | {} | {}",
+        name,
+        center.re,
+        center.im,
+        view.max.re - view.min.re,
+        args.max_iter,
+        time,
+        HELP
+    )
+}
+
+// a finished render pass, scaled up to the full grid
+struct Pass {
+    // samples of the full grid each one stands in for, across and down
+    scale: usize,
+    escapes: Vec<Escape>,
+    // since the first pass started
+    elapsed: Duration,
+}
+
+// each coarse sample repeated over the block of the full grid it stands for
+fn upscale(coarse: &[Escape], cols: usize, rows: usize, scale: usize) -> Vec<Escape> {
+    let coarse_cols = cols.div_ceil(scale);
+    (0..rows)
+        .flat_map(|y| (0..cols).map(move |x| coarse[y / scale * coarse_cols + x / scale]))
+        .collect()
+}
+
+// render the view in passes on another thread, from coarse to fine, sending
+// each one back as it's done. cancelling the Ifs stops it
+fn start(
+    args: &Args,
+    precision: Precision,
+    cols: usize,
+    rows: usize,
+) -> (Arc<Ifs>, Receiver<Pass>) {
+    let mandel = Arc::new(crate::fractal(args));
+    let (passes, receiver) = mpsc::channel();
+    let (args, worker) = (args.clone(), Arc::clone(&mandel));
+    thread::spawn(move || {
+        let start = Instant::now();
+        for scale in PASSES {
+            let (coarse_cols, coarse_rows) = (cols.div_ceil(scale), rows.div_ceil(scale));
+            let coarse = crate::render_escapes(&args, precision, &worker, coarse_cols, coarse_rows);
+            if worker.is_cancelled() {
+                return;
+            }
+            let pass = Pass {
+                scale,
+                escapes: upscale(&coarse, cols, rows, scale),
+                elapsed: start.elapsed(),
+            };
+            if passes.send(pass).is_err() {
+                return;
+            }
+        }
+    });
+    (mandel, receiver)
+}
+
+// draw a pass to fill the terminal, less a line for the status, giving back
+// the lines drawn
+fn draw(
+    out: &mut impl Write,
+    args: &Args,
+    colors: &Colors,
+    pass: &Pass,
+    size: (u16, u16),
+) -> io::Result<Vec<String>> {
+    if size.0 == 0 {
+        return Ok(Vec::new());
+    }
+    let (sx, _) = args.renderer.samples();
+    let grid = args.mapping.intensities(&pass.escapes, args.max_iter);
+    let lines = args
+        .renderer
+        .render(&grid, size.0 as usize * sx, colors, &args.ramp);
+    for (row, line) in lines.iter().enumerate() {
+        queue!(out, cursor::MoveTo(0, row as u16), Print(line))?;
+    }
+    queue!(
+        out,
+        cursor::MoveTo(0, size.1),
+        SetAttribute(Attribute::Reverse),
+        Print(format!("{:1$.1$}", status(args, pass), size.0 as usize)),
+        SetAttribute(Attribute::Reset)
+    )?;
+    out.flush()?;
+    Ok(lines)
+}
+
+// run the explorer until the user quits, starting from the view in args
+pub fn run(args: &Args, precision: Precision) -> io::Result<()> {
+    let mut args = args.clone();
+    let colors = Colors {
+        mode: args.color.unwrap_or_else(ColorMode::detect),
+        palette: args.palette,
+    };
+
+    let _screen = Screen::enter()?;
+    let mut out = io::stdout();
+    let mut drag: Option<Drag> = None;
+    loop {
+        let size = grid_size()?;
+        let (sx, sy) = args.renderer.samples();
+        let (mandel, passes) = start(&args, precision, size.0 as usize * sx, size.1 as usize * sy);
+        let mut lines = Vec::new();
+        let mut finished = false;
+
+        loop {
+            // show the finest pass that's ready, skipping any that were
+            // overtaken while waiting
+            if let Some(pass) = passes.try_iter().last() {
+                lines = draw(&mut out, &args, &colors, &pass, size)?;
+                finished = pass.scale == 1;
+                if let Some(drag) = drag.filter(|drag| drag.from != drag.to) {
+                    select(&mut out, &lines, drag)?;
+                }
+            }
+            // wait for input, checking back for passes until the last one
+            if !finished && !event::poll(POLL)? {
+                continue;
+            }
+
+            let action = match event::read()? {
+                Event::Key(event) => key(&mut args, event),
+                Event::Mouse(event) => mouse(&mut args, &mut drag, event)?,
+                Event::Resize(..) => {
+                    drag = None;
+                    queue!(out, Clear(ClearType::All))?;
+                    Action::Redraw
+                }
+            };
+            match action {
+                Action::Quit => {
+                    mandel.cancel();
+                    return Ok(());
+                }
+                Action::Redraw => break,
+                Action::Select => select(&mut out, &lines, drag.expect("dragging"))?,
+                Action::Ignore => (),
+            }
+        }
+        mandel.cancel();
+    }
+}
@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Escape-time formulas, selectable by name. Besides looking different, each
+// leans on a different part of the float implementation: abs for the
+// burning ship, negation for the tricorn, and powf's log/exp/trig for real
+// multibrot exponents.
+
+use crate::fractal::Viewport;
+use crate::precision::Real;
+use num::complex::Complex;
+use std::fmt;
+
+// a discrete dynamical system: a step function and a test for whether to
+// keep stepping
+pub trait Dds<State> {
+    fn cont(&self, z: State) -> bool;
+    fn next(&self, z: State, c: State) -> State;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Formula {
+    // z^2 + c
+    Mandelbrot,
+    // (|re z| + i|im z|)^2 + c
+    BurningShip,
+    // conj(z)^2 + c
+    Tricorn,
+    // z^n + c, by repeated multiplication
+    Multibrot(i32),
+    // z^p + c, through polar form
+    MultibrotReal(f64),
+}
+
+impl Formula {
+    // one of each kind, for cycling through
+    pub const ALL: [Self; 5] = [
+        Formula::Mandelbrot,
+        Formula::BurningShip,
+        Formula::Tricorn,
+        Formula::Multibrot(3),
+        Formula::MultibrotReal(2.5),
+    ];
+
+    // parse a name as printed by Display. multibrot takes its power after a
+    // colon, which must be above 1 for the escape radius of 2 to hold
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mandelbrot" => Some(Formula::Mandelbrot),
+            "burning-ship" => Some(Formula::BurningShip),
+            "tricorn" => Some(Formula::Tricorn),
+            _ => {
+                let power: f64 = name.strip_prefix("multibrot:")?.parse().ok()?;
+                if !power.is_finite() || power <= 1.0 {
+                    None
+                } else if power.fract() == 0.0 && power <= i32::MAX as f64 {
+                    Some(Formula::Multibrot(power as i32))
+                } else {
+                    Some(Formula::MultibrotReal(power))
+                }
+            }
+        }
+    }
+
+    // the power z is raised to, which sets how fast points escape
+    pub fn degree(self) -> f64 {
+        match self {
+            Formula::Mandelbrot | Formula::BurningShip | Formula::Tricorn => 2.0,
+            Formula::Multibrot(power) => power as f64,
+            Formula::MultibrotReal(power) => power,
+        }
+    }
+
+    // a viewport showing the whole set
+    pub fn viewport(self) -> Viewport {
+        match self {
+            Formula::Mandelbrot => Viewport::default(),
+            Formula::BurningShip => Viewport {
+                min: Complex::new(-2.2, -2.0),
+                max: Complex::new(1.4, 1.0),
+            },
+            Formula::Tricorn | Formula::Multibrot(_) | Formula::MultibrotReal(_) => Viewport {
+                min: Complex::new(-1.6, -1.6),
+                max: Complex::new(1.6, 1.6),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Formula::Mandelbrot => f.write_str("mandelbrot"),
+            Formula::BurningShip => f.write_str("burning-ship"),
+            Formula::Tricorn => f.write_str("tricorn"),
+            Formula::Multibrot(power) => write!(f, "multibrot:{}", power),
+            Formula::MultibrotReal(power) => write!(f, "multibrot:{}", power),
+        }
+    }
+}
+
+impl<T: Real> Dds<Complex<T>> for Formula {
+    fn cont(&self, z: Complex<T>) -> bool {
+        z.norm_sqr() <= T::from_f64(4.0)
+    }
+
+    fn next(&self, z: Complex<T>, c: Complex<T>) -> Complex<T> {
+        match *self {
+            Formula::Mandelbrot => z * z + c,
+            Formula::BurningShip => {
+                let z = Complex::new(z.re.abs(), z.im.abs());
+                z * z + c
+            }
+            Formula::Tricorn => {
+                let z = z.conj();
+                z * z + c
+            }
+            Formula::Multibrot(power) => z.powi(power) + c,
+            Formula::MultibrotReal(power) => z.powf(T::from_f64(power)) + c,
+        }
+    }
+}
@@ -0,0 +1,541 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// The fractal engine: iterated function systems over a generic float type,
+// and evaluation of whole viewports into grids of iteration counts.
+
+use crate::formula::{Dds, Formula};
+use crate::precision::{self, Precision, Real};
+use crate::subdivide::subdivide_grid;
+use num::complex::Complex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+// configure max iterations based on CPU features
+#[cfg(feature = "u64")]
+pub type Iter = u64;
+#[cfg(not(feature = "u64"))]
+pub type Iter = u32;
+
+// width of the iteration counter, for reporting
+pub const ITER_BITS: u32 = Iter::BITS;
+
+// default viewport and iteration limit, covering the whole set
+pub const VIEW_MIN: Complex<f64> = Complex::new(-1.4, -1.0);
+pub const VIEW_MAX: Complex<f64> = Complex::new(0.6, 1.0);
+pub const MAX_ITER: Iter = 256;
+
+// bailout radius for smooth iteration counts. the fractional part comes from
+// log log |z|, which only behaves once |z| is well past 2
+pub const SMOOTH_BAILOUT: f64 = 256.0;
+
+// iterations taken to escape, fractional for smooth counts, or None for
+// points that never did
+pub type Escape = Option<f64>;
+
+// named constants for julia mode, picked for their shapes
+pub const JULIA_PRESETS: [(&str, Complex<f64>); 6] = [
+    ("rabbit", Complex::new(-0.123, 0.745)),
+    ("dendrite", Complex::new(0.0, 1.0)),
+    ("siegel", Complex::new(-0.390541, -0.586788)),
+    ("san-marco", Complex::new(-0.75, 0.0)),
+    ("airplane", Complex::new(-1.754878, 0.0)),
+    ("spiral", Complex::new(-0.8, 0.156)),
+];
+
+pub fn julia_preset(name: &str) -> Option<Complex<f64>> {
+    JULIA_PRESETS
+        .iter()
+        .find(|(preset, _)| *preset == name)
+        .map(|&(_, c)| c)
+}
+
+// a rectangle of the complex plane to render
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub min: Complex<f64>,
+    pub max: Complex<f64>,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            min: VIEW_MIN,
+            max: VIEW_MAX,
+        }
+    }
+}
+
+impl Viewport {
+    // square around the origin, big enough for any connected julia set
+    pub const JULIA: Self = Self {
+        min: Complex::new(-1.8, -1.8),
+        max: Complex::new(1.8, 1.8),
+    };
+
+    // the default viewport's shape, scaled by zoom and moved to center
+    pub fn centered(center: Complex<f64>, zoom: f64) -> Self {
+        Self::default().zoomed(center, zoom)
+    }
+
+    // this viewport's shape, scaled by zoom and moved to center
+    pub fn zoomed(&self, center: Complex<f64>, zoom: f64) -> Self {
+        let half = (self.max - self.min).unscale(2.0 * zoom);
+        Self {
+            min: center - half,
+            max: center + half,
+        }
+    }
+
+    // this viewport magnified by zoom, keeping the point about where it is
+    pub fn zoomed_about(&self, about: Complex<f64>, zoom: f64) -> Self {
+        Self {
+            min: about + (self.min - about).unscale(zoom),
+            max: about + (self.max - about).unscale(zoom),
+        }
+    }
+
+    // this viewport moved by fractions of its width and height
+    pub fn panned(&self, dx: f64, dy: f64) -> Self {
+        let size = self.max - self.min;
+        let shift = Complex::new(size.re * dx, size.im * dy);
+        Self {
+            min: self.min + shift,
+            max: self.max + shift,
+        }
+    }
+
+    pub fn center(&self) -> Complex<f64> {
+        (self.min + self.max).unscale(2.0)
+    }
+}
+
+// ways to stop iterating a point early once it's known to be inside the set.
+// both are off by default, so every interior point runs to the iteration
+// limit and the FPU gets the full workout
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Shortcuts {
+    // test for the main cardioid and period-2 bulb before iterating, for the
+    // plain mandelbrot set only
+    pub bulbs: bool,
+    // stop when the orbit lands exactly on an earlier value, as it must then
+    // cycle forever
+    pub periodicity: bool,
+}
+
+impl Shortcuts {
+    pub const ALL: Self = Self {
+        bulbs: true,
+        periodicity: true,
+    };
+
+    // parse a comma-separated list of bulbs, periodicity, all or none
+    pub fn from_names(names: &str) -> Option<Self> {
+        let mut shortcuts = Self::default();
+        for name in names.split(',') {
+            match name {
+                "bulbs" => shortcuts.bulbs = true,
+                "periodicity" => shortcuts.periodicity = true,
+                "all" => shortcuts = Self::ALL,
+                "none" => shortcuts = Self::default(),
+                _ => return None,
+            }
+        }
+        Some(shortcuts)
+    }
+
+    pub fn any(self) -> bool {
+        self.bulbs || self.periodicity
+    }
+}
+
+// pixels each shortcut settled without running to the iteration limit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Saved {
+    pub bulbs: usize,
+    pub periodicity: usize,
+}
+
+// functions to calculate the mandelbrot set (or another formula's) for a
+// given point, or the julia set for a fixed parameter
+pub struct Ifs {
+    max_iter: Iter,
+    formula: Formula,
+    julia: Option<Complex<f64>>,
+    shortcuts: Shortcuts,
+    // counted as points are iterated, from however many threads
+    saved_bulbs: AtomicUsize,
+    saved_periodicity: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl Ifs {
+    pub fn new(max_iter: Iter) -> Self {
+        Self {
+            max_iter,
+            formula: Formula::Mandelbrot,
+            julia: None,
+            shortcuts: Shortcuts::default(),
+            saved_bulbs: AtomicUsize::new(0),
+            saved_periodicity: AtomicUsize::new(0),
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    // julia set for the parameter c, where each point is a starting value
+    pub fn julia(max_iter: Iter, c: Complex<f64>) -> Self {
+        Self {
+            julia: Some(c),
+            ..Self::new(max_iter)
+        }
+    }
+
+    // iterate a different formula
+    pub fn with_formula(self, formula: Formula) -> Self {
+        Self { formula, ..self }
+    }
+
+    // skip iterating points known to be inside the set
+    pub fn with_shortcuts(self, shortcuts: Shortcuts) -> Self {
+        Self { shortcuts, ..self }
+    }
+
+    // pixels settled by each shortcut so far
+    pub fn saved(&self) -> Saved {
+        Saved {
+            bulbs: self.saved_bulbs.load(Ordering::Relaxed),
+            periodicity: self.saved_periodicity.load(Ordering::Relaxed),
+        }
+    }
+
+    // give up on a render in progress on another thread: from now on every
+    // point escapes straight away, so it finishes quickly with a grid that
+    // should be thrown away
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    // whether a point is in the main cardioid or the period-2 bulb
+    fn in_bulbs<T: Real>(point: Complex<T>) -> bool {
+        let quarter = T::from_f64(0.25);
+        let y2 = point.im * point.im;
+        let x = point.re - quarter;
+        let q = x * x + y2;
+        let x1 = point.re + T::one();
+        q * (q + x) <= quarter * y2 || x1 * x1 + y2 <= quarter * quarter
+    }
+
+    // iterate from a point until cont fails or the limit is reached, giving
+    // the steps taken and the final value. points settled by a shortcut give
+    // the limit, just as if they had run to it
+    fn orbit<T: Real>(
+        &self,
+        point: Complex<T>,
+        cont: impl Fn(Complex<T>) -> bool,
+    ) -> (Iter, Complex<T>) {
+        // the mandelbrot set starts at z = c rather than z = 0, saving an
+        // iteration that would always give c. that holds for every formula
+        // here, as they all map 0 to c
+        let c = match self.julia {
+            Some(c) => precision::complex(c),
+            None => point,
+        };
+        let mut i: Iter = 0;
+        let mut z = point;
+        if self.is_cancelled() {
+            return (i, z);
+        }
+
+        if self.shortcuts.bulbs
+            && self.julia.is_none()
+            && self.formula == Formula::Mandelbrot
+            && Self::in_bulbs(point)
+        {
+            self.saved_bulbs.fetch_add(1, Ordering::Relaxed);
+            return (self.max_iter, z);
+        }
+
+        if !self.shortcuts.periodicity {
+            while i < self.max_iter && cont(z) {
+                z = self.formula.next(z, c);
+                i += 1;
+            }
+            return (i, z);
+        }
+
+        // brent's method: compare against a saved value, saved again at
+        // doubling intervals so a cycle of any length is caught
+        let (mut saved, mut interval, mut steps) = (z, 1, 0);
+        while i < self.max_iter && cont(z) {
+            z = self.formula.next(z, c);
+            i += 1;
+            if z == saved {
+                self.saved_periodicity.fetch_add(1, Ordering::Relaxed);
+                return (self.max_iter, z);
+            }
+            steps += 1;
+            if steps == interval {
+                (saved, interval, steps) = (z, interval * 2, 0);
+            }
+        }
+        (i, z)
+    }
+
+    pub fn iter<T: Real>(&self, point: Complex<T>) -> Iter {
+        let (i, _) = self.orbit(point, |z| self.formula.cont(z));
+        if i < self.max_iter {
+            return self.max_iter - i;
+        }
+        0
+    }
+
+    // normalized iteration count: the whole iterations taken to pass the
+    // bailout radius, less how far past it the point went, on a log log scale
+    pub fn smooth<T: Real>(&self, point: Complex<T>) -> Escape {
+        let bailout = T::from_f64(SMOOTH_BAILOUT * SMOOTH_BAILOUT);
+        let (i, z) = self.orbit(point, |z| z.norm_sqr() <= bailout);
+        if i == self.max_iter {
+            return None;
+        }
+
+        // |z| ends up between the bailout radius and that to the power of
+        // the degree, so this is 0 to 1 for any point that took a step
+        let log_ratio = z.norm().ln() / T::from_f64(SMOOTH_BAILOUT.ln());
+        let frac = log_ratio.log2() / T::from_f64(self.formula.degree().log2());
+        let count = i as f64 + 1.0 - frac.to_f64().unwrap_or(0.0);
+        // max() also drops the NaN from a point that overflowed
+        Some(count.max(0.0).min(self.max_iter as f64))
+    }
+}
+
+// escape counts for a grid of values from Ifs::iter
+pub fn escapes(grid: &[Iter], max_iter: Iter) -> Vec<Escape> {
+    grid.iter()
+        .map(|&value| match value {
+            0 => None,
+            value => Some((max_iter - value) as f64),
+        })
+        .collect()
+}
+
+// the sample point for a cell of a viewport divided into cols x rows
+pub fn sample_point<T: Real>(
+    min: Complex<T>,
+    max: Complex<T>,
+    cols: usize,
+    rows: usize,
+    col: usize,
+    row: usize,
+) -> Complex<T> {
+    let x = min.re + (max.re - min.re) * T::from_usize(col) / T::from_usize(cols);
+    let y = min.im + (max.im - min.im) * T::from_usize(row) / T::from_usize(rows);
+    Complex::new(x, y)
+}
+
+// run job for each index up to count across threads, concatenating the
+// results in index order. jobs are handed out one at a time as they're picked
+// up, since some take far longer than others
+pub(crate) fn in_parallel<R: Send>(
+    count: usize,
+    threads: usize,
+    job: impl Fn(usize) -> Vec<R> + Sync,
+) -> Vec<R> {
+    if threads <= 1 || count <= 1 {
+        return (0..count).flat_map(job).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut done: Vec<(usize, Vec<R>)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.min(count))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= count {
+                            return done;
+                        }
+                        done.push((index, job(index)));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("render thread panicked"))
+            .collect()
+    });
+    done.sort_unstable_by_key(|&(index, _)| index);
+    done.into_iter().flat_map(|(_, results)| results).collect()
+}
+
+// evaluate a function at each sample point of a viewport, a row per job.
+// each sample is computed the same way whichever thread does it, so the grid
+// is identical however many there are
+pub fn sample_grid<T: Real, R: Send>(
+    min: Complex<T>,
+    max: Complex<T>,
+    cols: usize,
+    rows: usize,
+    threads: usize,
+    f: impl Fn(Complex<T>) -> R + Sync,
+) -> Vec<R> {
+    in_parallel(rows, threads, |row| {
+        (0..cols)
+            .map(|col| f(sample_point(min, max, cols, rows, col, row)))
+            .collect()
+    })
+}
+
+// calculate iteration counts for a viewport, row by row
+pub fn render_grid<T: Real>(
+    mandel: &Ifs,
+    min: Complex<T>,
+    max: Complex<T>,
+    cols: usize,
+    rows: usize,
+    threads: usize,
+) -> Vec<Iter> {
+    sample_grid(min, max, cols, rows, threads, |c| mandel.iter(c))
+}
+
+// a value worked out at each sample point of a grid, at any precision
+pub trait Sample: Sync {
+    type Output: Copy + PartialEq + Send;
+    fn eval<T: Real>(&self, point: Complex<T>) -> Self::Output;
+
+    // whether a value means the point is inside the set, which subdivision
+    // fills in. nothing is, unless said otherwise
+    fn inside(&self, _value: Self::Output) -> bool {
+        false
+    }
+}
+
+// iteration counts, as from Ifs::iter
+pub struct Iters<'a>(pub &'a Ifs);
+
+impl Sample for Iters<'_> {
+    type Output = Iter;
+
+    fn eval<T: Real>(&self, point: Complex<T>) -> Iter {
+        self.0.iter(point)
+    }
+
+    fn inside(&self, value: Iter) -> bool {
+        value == 0
+    }
+}
+
+// smooth iteration counts, as from Ifs::smooth
+pub struct Smooth<'a>(pub &'a Ifs);
+
+impl Sample for Smooth<'_> {
+    type Output = Escape;
+
+    fn eval<T: Real>(&self, point: Complex<T>) -> Escape {
+        self.0.smooth(point)
+    }
+
+    fn inside(&self, value: Escape) -> bool {
+        value.is_none()
+    }
+}
+
+// how the cells of a grid are worked out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Method {
+    // every sample point, a row at a time
+    Sample,
+    // only as many points as it takes, see subdivide.rs
+    Subdivide,
+}
+
+// a grid of samples over a viewport, which can be rendered at any precision
+pub struct Grid<'a, S> {
+    sample: &'a S,
+    min: Complex<f64>,
+    max: Complex<f64>,
+    cols: usize,
+    rows: usize,
+    threads: usize,
+    method: Method,
+}
+
+impl<'a, S: Sample> Grid<'a, S> {
+    pub fn new(
+        sample: &'a S,
+        min: Complex<f64>,
+        max: Complex<f64>,
+        cols: usize,
+        rows: usize,
+        threads: usize,
+    ) -> Self {
+        Self {
+            sample,
+            min,
+            max,
+            cols,
+            rows,
+            threads,
+            method: Method::Sample,
+        }
+    }
+
+    pub fn with_method(self, method: Method) -> Self {
+        Self { method, ..self }
+    }
+
+    // the one place the precision is picked, with the corners converted
+    pub fn render(&self, precision: Precision) -> Vec<S::Output> {
+        match precision {
+            Precision::Single => self.render_as::<f32>(),
+            Precision::Double => self.render_as::<f64>(),
+        }
+    }
+
+    fn render_as<T: Real>(&self) -> Vec<S::Output> {
+        let (min, max) = (
+            precision::complex::<T>(self.min),
+            precision::complex(self.max),
+        );
+        let (cols, rows, threads) = (self.cols, self.rows, self.threads);
+        let f = |c| self.sample.eval(c);
+        match self.method {
+            Method::Sample => sample_grid(min, max, cols, rows, threads, f),
+            Method::Subdivide => subdivide_grid(min, max, cols, rows, threads, f, |value| {
+                self.sample.inside(value)
+            }),
+        }
+    }
+}
+
+// calculate iteration counts for a viewport at the given precision
+pub fn render(
+    precision: Precision,
+    mandel: &Ifs,
+    min: Complex<f64>,
+    max: Complex<f64>,
+    cols: usize,
+    rows: usize,
+    threads: usize,
+) -> Vec<Iter> {
+    Grid::new(&Iters(mandel), min, max, cols, rows, threads).render(precision)
+}
+
+// calculate smooth iteration counts for a viewport at the given precision
+pub fn render_smooth(
+    precision: Precision,
+    mandel: &Ifs,
+    min: Complex<f64>,
+    max: Complex<f64>,
+    cols: usize,
+    rows: usize,
+    threads: usize,
+) -> Vec<Escape> {
+    Grid::new(&Smooth(mandel), min, max, cols, rows, threads).render(precision)
+}
@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Image file export of iteration grids, one pixel per sample.
+// PNG files are written with uncompressed deflate blocks, which every decoder
+// handles, rather than pulling a compression library onto the target.
+
+use crate::color::{Colors, Palette, Rgb};
+use crate::mapping::Intensity;
+use std::io::{self, Write};
+use std::path::Path;
+
+// supported file formats
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    // binary greymap
+    Pgm,
+    // binary pixmap
+    Ppm,
+    Png,
+}
+
+impl ImageFormat {
+    // pick a format from a file name's extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "pgm" => Some(ImageFormat::Pgm),
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            _ => None,
+        }
+    }
+}
+
+// pixel colour for a sample, with the inside of the set black
+fn pixel(colors: &Colors, intensity: Intensity) -> Rgb {
+    colors.intensity_rgb(intensity).unwrap_or((0, 0, 0))
+}
+
+// write a grid `width` samples wide as an image. greymaps always use the
+// grey palette, the others use the one in `colors`
+pub fn write_image<W: Write>(
+    out: &mut W,
+    format: ImageFormat,
+    grid: &[Intensity],
+    width: usize,
+    colors: &Colors,
+) -> io::Result<()> {
+    let colors = match format {
+        ImageFormat::Pgm => Colors {
+            palette: Palette::Grey,
+            ..*colors
+        },
+        _ => *colors,
+    };
+    let pixels: Vec<Rgb> = grid.iter().map(|&t| pixel(&colors, t)).collect();
+    write_pixels(out, format, &pixels, width)
+}
+
+// write pixels `width` wide as an image, greymaps taking the luma
+pub fn write_pixels<W: Write>(
+    out: &mut W,
+    format: ImageFormat,
+    pixels: &[Rgb],
+    width: usize,
+) -> io::Result<()> {
+    let height = pixels.len() / width;
+
+    match format {
+        ImageFormat::Pgm => {
+            write!(out, "P5\n{} {}\n255\n", width, height)?;
+            let luma =
+                |(r, g, b): Rgb| ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8;
+            let grey: Vec<u8> = pixels.iter().map(|&rgb| luma(rgb)).collect();
+            out.write_all(&grey)
+        }
+        ImageFormat::Ppm => {
+            write!(out, "P6\n{} {}\n255\n", width, height)?;
+            let mut raw = Vec::with_capacity(pixels.len() * 3);
+            for &(r, g, b) in pixels {
+                raw.extend_from_slice(&[r, g, b]);
+            }
+            out.write_all(&raw)
+        }
+        ImageFormat::Png => {
+            // each scanline starts with its filter type, always 0 (none)
+            let mut raw = Vec::with_capacity(height * (width * 3 + 1));
+            for row in pixels.chunks(width) {
+                raw.push(0);
+                for &(r, g, b) in row {
+                    raw.extend_from_slice(&[r, g, b]);
+                }
+            }
+            write_png(out, width as u32, height as u32, &raw)
+        }
+    }
+}
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// largest stored deflate block
+const STORED_BLOCK: usize = 0xffff;
+
+// write an 8-bit RGB PNG from filtered scanlines
+fn write_png<W: Write>(out: &mut W, width: u32, height: u32, raw: &[u8]) -> io::Result<()> {
+    out.write_all(&PNG_SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // bit depth 8, colour type 2 (RGB), default compression/filter, no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(out, b"IHDR", &header)?;
+
+    // zlib stream: header for deflate with a 32K window, stored blocks, adler32
+    let blocks = raw.len().div_ceil(STORED_BLOCK).max(1);
+    let mut zlib = Vec::with_capacity(raw.len() + blocks * 5 + 6);
+    zlib.extend_from_slice(&[0x78, 0x01]);
+    for i in 0..blocks {
+        let block = &raw[i * STORED_BLOCK..raw.len().min((i + 1) * STORED_BLOCK)];
+        let len = block.len() as u16;
+        zlib.push((i == blocks - 1) as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(raw).to_be_bytes());
+    write_chunk(out, b"IDAT", &zlib)?;
+
+    write_chunk(out, b"IEND", &[])
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let crc = !crc32_update(crc32_update(!0, kind), data);
+    out.write_all(&crc.to_be_bytes())
+}
+
+// CRC-32 (ISO 3309) lookup table
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+};
+
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc = CRC32_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    !crc32_update(!0, data)
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the most bytes that can be summed before b could overflow
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
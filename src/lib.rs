@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Simple little rust library to do some cute ASCII mandelbrot stuff.
+// Created to test compilation and execution of floating-point Rust code
+// in the OpenWrt build environment. The float_test binary is a thin
+// front-end over this.
+
+#![forbid(unsafe_code)]
+
+use shadow_rs::shadow;
+
+pub mod ascii;
+pub mod bench;
+pub mod color;
+pub mod cores;
+pub mod divergence;
+pub mod formula;
+pub mod fractal;
+pub mod image;
+pub mod mapping;
+pub mod newton;
+pub mod precision;
+pub mod reference;
+pub mod renderer;
+pub mod selftest;
+pub mod subdivide;
+
+pub use ascii::{render_ascii, val_to_char, Ramp};
+pub use color::{ColorMode, Colors, Palette};
+pub use formula::{Dds, Formula};
+pub use fractal::{
+    escapes, julia_preset, render, render_grid, render_smooth, sample_grid, sample_point, Escape,
+    Grid, Ifs, Iter, Iters, Method, Sample, Saved, Shortcuts, Smooth, Viewport, ITER_BITS,
+    JULIA_PRESETS, MAX_ITER, SMOOTH_BAILOUT, VIEW_MAX, VIEW_MIN,
+};
+pub use image::{write_image, write_pixels, ImageFormat};
+pub use mapping::{Intensity, Mapping};
+pub use newton::Newton;
+pub use precision::{Precision, Real};
+pub use renderer::Renderer;
+
+// gather build info
+shadow!(build);
+
+// some info about what we're doing, as printed at startup
+pub fn banner(precisions: &[Precision]) -> String {
+    let names: Vec<&str> = precisions.iter().map(|p| p.name()).collect();
+    format!(
+        "float_test v{} {} for {} ({} precision, {}-bit iterations)\nbuilt with {} at {} on a {} host",
+        build::PKG_VERSION,
+        build::BUILD_RUST_CHANNEL,
+        build::BUILD_TARGET,
+        names.join(" and "),
+        ITER_BITS,
+        build::RUST_VERSION,
+        build::BUILD_TIME_2822,
+        build::BUILD_OS,
+    )
+}
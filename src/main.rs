@@ -2,134 +2,473 @@
 //
 // Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
 //
-// Simple little rust program to do some cute ASCII mandelbrot stuff.
-// Created to test compilation and execution of floating-point Rust code
-// in the OpenWrt build environment.
+// Command-line front-end for the float_test library: renders the set, runs
+// the self-tests and reports the results.
 
 #![forbid(unsafe_code)]
 
 use crossterm::terminal;
-use num::complex::Complex;
-use shadow_rs::shadow;
-use std::cmp;
-
-// gather build info
-shadow!(build);
-
-// configure floating-point precision based on CPU features
-#[cfg(feature = "f32")]
-pub type Float = f32;
-#[cfg(feature = "f32")]
-const PRECISION: &str = "single";
-#[cfg(not(feature = "f32"))]
-pub type Float = f64;
-#[cfg(not(feature = "f32"))]
-const PRECISION: &str = "double";
-
-// flexible-precision complex number type
-pub type FlexComplex = Complex<Float>;
-
-// configure max iterations based on CPU features
-#[cfg(feature = "u64")]
-pub type Iter = u64;
-#[cfg(not(feature = "u64"))]
-pub type Iter = u32;
-
-// functions to calculate the mandelbrot set for a given point
-struct Ifs {
-    max_iter: Iter,
-}
+use float_test::bench::{self, Bench};
+use float_test::color::Rgb;
+use float_test::{cores, divergence, newton, reference, selftest};
+use float_test::{escapes, write_image, write_pixels, ColorMode, Colors, Grid, Iters};
+use float_test::{Escape, Formula, Ifs, Method, Newton, Precision, Smooth, Viewport};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
 
-trait Dds<State> {
-    fn cont(&self, z: State) -> bool;
-    fn next(&self, z: State, c: State) -> State;
-}
+mod cli;
+mod explore;
+mod report;
+
+// at most this many diverging cells are listed in text output
+const MAX_DIFFS_SHOWN: usize = 32;
 
-impl Dds<FlexComplex> for Ifs {
-    fn cont(&self, z: FlexComplex) -> bool {
-        z.norm_sqr() <= 4.0
+// print the results for one precision
+fn print_run(run: &selftest::Run) {
+    let failed = run.failed();
+    let reference = &run.reference;
+
+    println!("{} precision:", run.precision);
+    for check in &run.checks {
+        println!(
+            "{} {}",
+            if check.passed { "PASS" } else { "FAIL" },
+            check.name
+        );
     }
+    println!(
+        "{}/{} checks passed",
+        run.checks.len() - failed,
+        run.checks.len()
+    );
 
-    fn next(&self, z: FlexComplex, c: FlexComplex) -> FlexComplex {
-        z * z + c
+    if reference.passed() {
+        println!("PASS reference grid (hash {:#018x})", reference.hash);
+    } else {
+        println!(
+            "FAIL reference grid (hash {:#018x}, expected {:#018x}), {}/{} cells differ",
+            reference.hash,
+            reference.expected_hash,
+            reference.diffs.len(),
+            reference::COLS * reference::ROWS
+        );
+        for diff in reference.diffs.iter().take(MAX_DIFFS_SHOWN) {
+            println!(
+                "  row {:2} col {:2}: expected {}, got {} ({:+})",
+                diff.row,
+                diff.col,
+                diff.expected,
+                diff.actual,
+                diff.delta()
+            );
+        }
+        if reference.diffs.len() > MAX_DIFFS_SHOWN {
+            println!("  ... and {} more", reference.diffs.len() - MAX_DIFFS_SHOWN);
+        }
     }
 }
 
-impl Ifs {
-    pub fn new(max_iter: Iter) -> Self {
-        Self { max_iter }
+// print the timings for one precision
+fn print_bench(bench: &Bench) {
+    println!(
+        "{} precision: {} rounds of {}x{} on {} thread{}",
+        bench.precision,
+        bench.rounds,
+        bench::COLS,
+        bench::ROWS,
+        bench.threads,
+        if bench.threads == 1 { "" } else { "s" }
+    );
+    println!(
+        "{} iterations in {:.3} s, {:.1}M iterations/s, {:.0} ns/pixel",
+        bench.iterations,
+        bench.elapsed.as_secs_f64(),
+        bench.iterations_per_sec() / 1e6,
+        bench.ns_per_pixel()
+    );
+}
+
+// run the IEEE-754 checks and reference comparison for each precision (plus
+// the cross-precision checks if there's more than one), print the results
+// along with any benchmarks and return the verdict
+fn run_selftest(precisions: &[Precision], benches: &[Bench], format: cli::Report) -> bool {
+    let runs: Vec<selftest::Run> = precisions.iter().map(|&p| selftest::Run::new(p)).collect();
+    let cross = match precisions.len() {
+        1 => Vec::new(),
+        _ => selftest::cross(),
+    };
+    let passed = runs.iter().all(|run| run.passed()) && cross.iter().all(|c| c.passed);
+
+    match format {
+        cli::Report::Text => {
+            for run in &runs {
+                print_run(run);
+            }
+            if !cross.is_empty() {
+                println!("cross-precision:");
+                for check in &cross {
+                    println!(
+                        "{} {}",
+                        if check.passed { "PASS" } else { "FAIL" },
+                        check.name
+                    );
+                }
+            }
+            for bench in benches {
+                print_bench(bench);
+            }
+        }
+        cli::Report::Json => println!("{}", report::json(&runs, &cross, benches, passed)),
     }
+    passed
+}
+
+// render the reference grid pinned to each CPU, print the hashes and return
+// whether they all agreed
+fn run_per_core(precisions: &[Precision]) -> bool {
+    let mut passed = true;
+    for &precision in precisions {
+        let runs = match cores::check(precision) {
+            Ok(runs) => runs,
+            Err(err) => {
+                eprintln!("float_test: can't list CPUs: {}", err);
+                return false;
+            }
+        };
+        let consensus = cores::consensus(&runs, precision);
 
-    pub fn iter(&self, c: FlexComplex) -> Iter {
-        let mut i: Iter = 0;
-        let mut z = c;
-        while i < self.max_iter && self.cont(z) {
-            z = self.next(z, c);
-            i += 1;
+        println!("{} precision on {} CPUs:", precision, runs.len());
+        let mut bad = 0;
+        for run in &runs {
+            match run.hash {
+                Ok(hash) if Some(hash) == consensus => {
+                    println!("PASS cpu {:3} hash {:#018x}", run.cpu, hash)
+                }
+                Ok(hash) => {
+                    println!("FAIL cpu {:3} hash {:#018x} differs", run.cpu, hash);
+                    bad += 1;
+                }
+                Err(ref err) => {
+                    println!("FAIL cpu {:3} can't pin: {}", run.cpu, err);
+                    bad += 1;
+                }
+            }
         }
-        if i < self.max_iter {
-            return self.max_iter - i;
+        if consensus != Some(reference::expected_hash(precision)) {
+            println!("note: the cores agree on a hash that differs from the reference grid");
         }
-        0
+        println!("{}/{} CPUs agree", runs.len() - bad, runs.len());
+        passed &= bad == 0;
     }
+    passed
 }
 
-// changes an intensity into an ascii character
-fn val_to_char(value: u8) -> char {
-    let chars = ['@', '%', '#', '*', '+', '=', '~', ':', '.', ' '];
+// work out the render size from the terminal unless it was given, and clamp
+// it to something reasonable unless asked not to
+fn render_size(args: &cli::Args) -> (usize, usize) {
+    let termsize: (u16, u16) = terminal::size().unwrap_or((80, 25));
+    // keep the terminal's size to something reasonable, but use an explicit
+    // --width or --height as given
+    let clamp = |size: u16, min, max| {
+        if args.no_clamp {
+            size as usize
+        } else {
+            (size as usize).clamp(min, max)
+        }
+    };
+    let cols = args.width.unwrap_or_else(|| clamp(termsize.0, 80, 128));
+    let rows = args.height.unwrap_or_else(|| clamp(termsize.1, 40, 128));
 
-    let num_chars: u8 = chars.len() as u8;
-    let step: u8 = (255 / num_chars) as u8;
+    println!(
+        "{}x{} terminal, will output {}x{} characters",
+        termsize.0, termsize.1, cols, rows
+    );
+    (cols, rows)
+}
 
-    for i in 0..(num_chars - 1) {
-        if value >= i * step && value < (i + 1) * step {
-            return chars[i as usize];
+// render both precisions and show where and by how much they disagree
+fn run_compare(view: Viewport, mandel: &Ifs, cols: usize, rows: usize, threads: usize) {
+    let divergence = divergence::Divergence::new(mandel, view.min, view.max, cols, rows, threads);
+
+    for line in divergence.map() {
+        println!("{}", line);
+    }
+    println!(
+        "{}/{} cells differ ({:.2}%), max difference {}, mean {:.2}",
+        divergence.differing(),
+        divergence.deltas.len(),
+        divergence.fraction() * 100.0,
+        divergence.max_delta(),
+        divergence.mean_delta()
+    );
+    for (i, &count) in divergence.histogram().iter().enumerate() {
+        if count == 0 {
+            continue;
         }
+        let low = 1u64 << i;
+        let range = match i {
+            _ if i == divergence::BUCKETS - 1 => format!("{}+", low),
+            0 => "1".to_string(),
+            _ => format!("{}-{}", low, 2 * low - 1),
+        };
+        println!(
+            "  '{}' {:>8} iterations: {} cells",
+            divergence::diff_char(low as i64),
+            range,
+            count
+        );
     }
-    chars[(num_chars - 1) as usize]
 }
 
-// main execution
-fn main() {
-    // work out what size terminal we have to work with
-    let termsize: (u16, u16) = terminal::size().unwrap_or((80, 25));
+// default image size when not given
+const IMAGE_SIZE: usize = 512;
 
-    // clamp minimum and maximum dimensions to something reasonable
-    let cols = cmp::min(cmp::max(termsize.0 as usize, 80), 128);
-    let rows = cmp::min(cmp::max(termsize.1 as usize, 40), 128);
+// render straight to an image file
+fn write_output(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write(&mut file)?;
+    file.flush()
+}
 
-    // print some info about what we're doing
+// render a newton fractal as text or to an image file
+fn run_newton(args: &cli::Args, coeffs: &[f64], precision: Precision) -> ExitCode {
+    let newton = Newton::new(args.max_iter, coeffs).expect("checked when parsing");
+    let view = args.viewport;
     println!(
-        "float_test v{} {} for {} ({} precision)",
-        build::PKG_VERSION,
-        build::BUILD_RUST_CHANNEL,
-        build::BUILD_TARGET,
-        PRECISION
+        "newton fractal for {} ({} roots)",
+        newton,
+        newton.roots().len()
     );
-    println!(
-        "built with {} at {} on a {} host",
-        build::RUST_VERSION,
-        build::BUILD_TIME_2822,
-        build::BUILD_OS,
+
+    if let Some((path, format)) = &args.output {
+        let width = args.width.unwrap_or(IMAGE_SIZE);
+        let height = args.height.unwrap_or(IMAGE_SIZE);
+        let grid = newton::render(
+            precision,
+            &newton,
+            view.min,
+            view.max,
+            width,
+            height,
+            args.threads,
+        );
+        let pixels: Vec<Rgb> = grid
+            .iter()
+            .map(|&basin| newton.rgb(basin).unwrap_or((0, 0, 0)))
+            .collect();
+
+        if let Err(err) = write_output(path, |out| write_pixels(out, *format, &pixels, width)) {
+            eprintln!("float_test: can't write {}: {}", path.display(), err);
+            return ExitCode::FAILURE;
+        }
+        println!("wrote {}x{} image to {}", width, height, path.display());
+        return ExitCode::SUCCESS;
+    }
+
+    let (cols, rows) = render_size(args);
+    let grid = newton::render(
+        precision,
+        &newton,
+        view.min,
+        view.max,
+        cols,
+        rows,
+        args.threads,
     );
+    let colors = Colors {
+        mode: args.color.unwrap_or_else(ColorMode::detect),
+        palette: args.palette,
+    };
+    for line in newton::render_ascii(&newton, &grid, cols, &colors, &args.ramp) {
+        println!("{}", line);
+    }
+    ExitCode::SUCCESS
+}
+
+// the fractal selected on the command line
+fn fractal(args: &cli::Args) -> Ifs {
+    let fractal = match args.julia {
+        Some(c) => Ifs::julia(args.max_iter, c),
+        None => Ifs::new(args.max_iter),
+    };
+    fractal
+        .with_formula(args.formula)
+        .with_shortcuts(args.shortcuts)
+}
+
+// how many of the pixels the shortcuts settled, if any were enabled
+fn print_saved(args: &cli::Args, mandel: &Ifs, pixels: usize) {
+    if !args.shortcuts.any() {
+        return;
+    }
+    let saved = mandel.saved();
     println!(
-        "{}x{} terminal, will output {}x{} characters",
-        termsize.0, termsize.1, cols, rows
+        "shortcuts settled {} of {} pixels: {} in the cardioid or bulb, {} by periodicity",
+        saved.bulbs + saved.periodicity,
+        pixels,
+        saved.bulbs,
+        saved.periodicity
     );
+}
+
+// escape counts for the selected viewport, smoothed if asked for
+fn render_escapes(
+    args: &cli::Args,
+    precision: Precision,
+    mandel: &Ifs,
+    cols: usize,
+    rows: usize,
+) -> Vec<Escape> {
+    let (min, max, threads) = (args.viewport.min, args.viewport.max, args.threads);
+    // the explorer can switch formula, and only the mandelbrot set is
+    // subdivided
+    let method = if args.subdivide && args.julia.is_none() && args.formula == Formula::Mandelbrot {
+        Method::Subdivide
+    } else {
+        Method::Sample
+    };
+    if args.smooth {
+        return Grid::new(&Smooth(mandel), min, max, cols, rows, threads)
+            .with_method(method)
+            .render(precision);
+    }
+    let grid = Grid::new(&Iters(mandel), min, max, cols, rows, threads)
+        .with_method(method)
+        .render(precision);
+    escapes(&grid, args.max_iter)
+}
+
+// main execution
+fn main() -> ExitCode {
+    let args = match cli::Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("float_test: {}\n\n{}", err, cli::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    if args.help {
+        println!("{}", cli::USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    if args.dump_grid {
+        for &precision in &args.precisions {
+            print!("{}", reference::dump(precision));
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    if args.per_core {
+        println!("{}", float_test::banner(&args.precisions));
+        return if run_per_core(&args.precisions) {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        };
+    }
+
+    let benches: Vec<Bench> = match args.bench {
+        true => Precision::ALL
+            .iter()
+            .map(|&p| Bench::run(p, args.threads))
+            .collect(),
+        false => Vec::new(),
+    };
+
+    // the JSON report replaces all other output, so it always runs the checks
+    if args.selftest || args.report == cli::Report::Json {
+        if args.report == cli::Report::Text {
+            println!("{}", float_test::banner(&args.precisions));
+        }
+        return if run_selftest(&args.precisions, &benches, args.report) {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        };
+    }
+
+    if args.bench {
+        println!("{}", float_test::banner(&Precision::ALL));
+        for bench in &benches {
+            print_bench(bench);
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    if args.compare {
+        println!("{}", float_test::banner(&Precision::ALL));
+        let (cols, rows) = render_size(&args);
+        run_compare(args.viewport, &fractal(&args), cols, rows, args.threads);
+        return ExitCode::SUCCESS;
+    }
 
-    // do math for and render mandelbrot set
-    let min = Complex::new(-1.4, -1.0);
-    let max = Complex::new(0.6, 1.0);
-    let mandel = Ifs::new(256);
-
-    for row in 0..rows {
-        for col in 0..cols {
-            let x = min.re + (max.re - min.re) * (col as Float) / (cols as Float);
-            let y = min.im + (max.im - min.im) * (row as Float) / (rows as Float);
-            let c = Complex::new(x, y);
-            let m = mandel.iter(c) as u8;
-            print!("{}", val_to_char(m));
+    let precision = match args.precisions[..] {
+        [precision] => precision,
+        _ => {
+            eprintln!("float_test: can only render at one precision");
+            return ExitCode::from(2);
         }
-        println!();
+    };
+    if args.explore {
+        return match explore::run(&args, precision) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("float_test: {}", err);
+                ExitCode::FAILURE
+            }
+        };
     }
+    println!("{}", float_test::banner(&args.precisions));
+    if let Some(coeffs) = &args.newton {
+        return run_newton(&args, coeffs, precision);
+    }
+    let mandel = fractal(&args);
+    match args.julia {
+        Some(c) => println!("{} julia set for c = {}{:+}i", args.formula, c.re, c.im),
+        None if args.formula != Formula::Mandelbrot => println!("{} set", args.formula),
+        None => (),
+    }
+
+    if let Some((path, format)) = &args.output {
+        let width = args.width.unwrap_or(IMAGE_SIZE);
+        let height = args.height.unwrap_or(IMAGE_SIZE);
+        let escapes = render_escapes(&args, precision, &mandel, width, height);
+        let grid = args.mapping.intensities(&escapes, args.max_iter);
+        let colors = Colors {
+            mode: ColorMode::TrueColor,
+            palette: args.palette,
+        };
+
+        let write = |out: &mut BufWriter<File>| write_image(out, *format, &grid, width, &colors);
+        if let Err(err) = write_output(path, write) {
+            eprintln!("float_test: can't write {}: {}", path.display(), err);
+            return ExitCode::FAILURE;
+        }
+        println!("wrote {}x{} image to {}", width, height, path.display());
+        print_saved(&args, &mandel, escapes.len());
+        return ExitCode::SUCCESS;
+    }
+
+    // do math for and render the set
+    let (cols, rows) = render_size(&args);
+    let (sx, sy) = args.renderer.samples();
+    let escapes = render_escapes(&args, precision, &mandel, cols * sx, rows * sy);
+    let grid = args.mapping.intensities(&escapes, args.max_iter);
+    let colors = Colors {
+        mode: args.color.unwrap_or_else(ColorMode::detect),
+        palette: args.palette,
+    };
+    for line in args.renderer.render(&grid, cols * sx, &colors, &args.ramp) {
+        println!("{}", line);
+    }
+    print_saved(&args, &mandel, escapes.len());
+
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Mapping of escape counts to intensities from 0 (escaped straight away) to
+// 1 (took the whole iteration limit), which the renderers then turn into
+// characters and colours. Keeping this separate means every renderer works
+// with any iteration limit, and any ramp or palette length.
+
+use crate::fractal::{Escape, Iter};
+
+// brightness of a sample from 0 to 1, or None for points inside the set
+pub type Intensity = Option<f64>;
+
+// bands per cycle when modulo isn't given one
+pub const MODULO_PERIOD: u32 = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mapping {
+    // proportional to the escape count
+    Linear,
+    // proportional to its logarithm, as most points escape early
+    Log,
+    // by rank, so each intensity covers an equal share of the samples
+    Histogram,
+    // cycling through the range every n iterations
+    Modulo(u32),
+}
+
+impl Mapping {
+    pub const ALL: [Self; 4] = [
+        Mapping::Linear,
+        Mapping::Log,
+        Mapping::Histogram,
+        Mapping::Modulo(MODULO_PERIOD),
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mapping::Linear => "linear",
+            Mapping::Log => "log",
+            Mapping::Histogram => "histogram",
+            Mapping::Modulo(_) => "modulo",
+        }
+    }
+
+    // parse a name, with modulo optionally taking its period after a colon
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(period) = name.strip_prefix("modulo:") {
+            return match period.parse() {
+                Ok(period) if period > 0 => Some(Mapping::Modulo(period)),
+                _ => None,
+            };
+        }
+        Self::ALL.iter().copied().find(|m| m.name() == name)
+    }
+
+    // intensities for a whole grid, as the histogram needs to see all of it
+    pub fn intensities(self, grid: &[Escape], max_iter: Iter) -> Vec<Intensity> {
+        let max = max_iter as f64;
+        match self {
+            Mapping::Linear => map(grid, |e| e / max),
+            Mapping::Log => map(grid, |e| e.ln_1p() / max.ln_1p()),
+            Mapping::Modulo(period) => {
+                let period = period as f64;
+                map(grid, |e| e.rem_euclid(period) / period)
+            }
+            Mapping::Histogram => {
+                let mut sorted: Vec<f64> = grid.iter().flatten().copied().collect();
+                sorted.sort_by(f64::total_cmp);
+                let count = sorted.len() as f64;
+                // fraction of escaping samples that escaped no later
+                map(grid, |e| sorted.partition_point(|&s| s <= e) as f64 / count)
+            }
+        }
+    }
+}
+
+fn map(grid: &[Escape], f: impl Fn(f64) -> f64) -> Vec<Intensity> {
+    grid.iter()
+        .map(|escape| escape.map(|e| f(e).clamp(0.0, 1.0)))
+        .collect()
+}
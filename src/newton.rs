@@ -0,0 +1,232 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Newton-Raphson fractals: each point is a starting guess for a root of a
+// polynomial, coloured by the root it converges to and how quickly. Every
+// step is a complex division, which the escape-time formulas never do, and
+// which is where soft-float implementations have gone wrong before.
+
+use crate::ascii::Ramp;
+use crate::color::{Colors, Palette, Rgb};
+use crate::formula::Dds;
+use crate::fractal::{Grid, Iter, Sample, Viewport};
+use crate::precision::{self, Precision, Real};
+use num::complex::Complex;
+use std::fmt;
+
+// the roots of z^3 - 1 sit on the unit circle
+pub const VIEW: Viewport = Viewport {
+    min: Complex::new(-1.5, -1.5),
+    max: Complex::new(1.5, 1.5),
+};
+
+// a point counts as converged this close to a root
+const TOLERANCE: f64 = 1e-3;
+
+// which root a point converged to and how many steps it took, or None if it
+// didn't within the iteration limit
+pub type Basin = Option<(usize, Iter)>;
+
+pub struct Newton {
+    max_iter: Iter,
+    // coefficients from the highest power down
+    coeffs: Vec<Complex<f64>>,
+    deriv: Vec<Complex<f64>>,
+    roots: Vec<Complex<f64>>,
+}
+
+impl Newton {
+    // z^3 - 1
+    pub fn cubic(max_iter: Iter) -> Self {
+        Self::new(max_iter, &[1.0, 0.0, 0.0, -1.0]).expect("z^3 - 1 is a valid polynomial")
+    }
+
+    // polynomial with real coefficients from the highest power down, which
+    // must be at least quadratic
+    pub fn new(max_iter: Iter, coeffs: &[f64]) -> Option<Self> {
+        let first = coeffs.iter().position(|&a| a != 0.0)?;
+        let coeffs: Vec<Complex<f64>> = coeffs[first..]
+            .iter()
+            .map(|&a| Complex::new(a, 0.0))
+            .collect();
+        if coeffs.len() < 3 || coeffs.iter().any(|a| !a.re.is_finite()) {
+            return None;
+        }
+
+        let degree = coeffs.len() - 1;
+        let deriv = coeffs[..degree]
+            .iter()
+            .enumerate()
+            .map(|(i, &a)| a.scale((degree - i) as f64))
+            .collect();
+        let roots = durand_kerner(&coeffs);
+        Some(Self {
+            max_iter,
+            coeffs,
+            deriv,
+            roots,
+        })
+    }
+
+    pub fn roots(&self) -> &[Complex<f64>] {
+        &self.roots
+    }
+
+    // the root nearest z, if it's within the tolerance
+    fn root<T: Real>(&self, z: Complex<T>) -> Option<usize> {
+        let tolerance = T::from_f64(TOLERANCE * TOLERANCE);
+        self.roots
+            .iter()
+            .position(|&root| (z - precision::complex(root)).norm_sqr() < tolerance)
+    }
+
+    pub fn iter<T: Real>(&self, z: Complex<T>) -> Basin {
+        let mut i: Iter = 0;
+        let mut z = z;
+        while i < self.max_iter && self.cont(z) {
+            z = self.next(z, Complex::new(T::zero(), T::zero()));
+            i += 1;
+        }
+        self.root(z).map(|root| (root, i))
+    }
+
+    // colour for a result: a hue per root, darker the longer it took
+    pub fn rgb(&self, basin: Basin) -> Option<Rgb> {
+        let (root, i) = basin?;
+        let hue = Palette::Rainbow.rgb(root as f64 / self.roots.len() as f64);
+        let shade = 1.0 - 0.8 * (i as f64).ln_1p() / (self.max_iter as f64).ln_1p();
+        let dim = |x: u8| (x as f64 * shade).round() as u8;
+        Some((dim(hue.0), dim(hue.1), dim(hue.2)))
+    }
+}
+
+// evaluate a polynomial at z by Horner's method
+fn horner<T: Real>(coeffs: &[Complex<f64>], z: Complex<T>) -> Complex<T> {
+    coeffs
+        .iter()
+        .fold(Complex::new(T::zero(), T::zero()), |acc, &a| {
+            acc * z + precision::complex(a)
+        })
+}
+
+// steps towards a root until one is reached. the parameter is unused, as a
+// newton fractal has no equivalent of the mandelbrot set's c
+impl<T: Real> Dds<Complex<T>> for Newton {
+    fn cont(&self, z: Complex<T>) -> bool {
+        self.root(z).is_none()
+    }
+
+    fn next(&self, z: Complex<T>, _c: Complex<T>) -> Complex<T> {
+        z - horner(&self.coeffs, z) / horner(&self.deriv, z)
+    }
+}
+
+impl Sample for Newton {
+    type Output = Basin;
+
+    fn eval<T: Real>(&self, z: Complex<T>) -> Basin {
+        self.iter(z)
+    }
+}
+
+impl fmt::Display for Newton {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let degree = self.coeffs.len() - 1;
+        let mut first = true;
+        for (i, a) in self.coeffs.iter().enumerate() {
+            let (a, power) = (a.re, degree - i);
+            if a == 0.0 {
+                continue;
+            }
+            let sign = if a < 0.0 { "-" } else { "+" };
+            match first {
+                true if a < 0.0 => f.write_str("-")?,
+                true => (),
+                false => write!(f, " {} ", sign)?,
+            }
+            first = false;
+            if a.abs() != 1.0 || power == 0 {
+                write!(f, "{}", a.abs())?;
+            }
+            match power {
+                0 => (),
+                1 => f.write_str("z")?,
+                _ => write!(f, "z^{}", power)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// all roots of a polynomial at once, by the Durand-Kerner method. this runs
+// once in double precision, to tell which root each point ends up at
+fn durand_kerner(coeffs: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let degree = coeffs.len() - 1;
+    let monic: Vec<Complex<f64>> = coeffs.iter().map(|&a| a / coeffs[0]).collect();
+    // powers of a number that's neither real nor a root of unity
+    let seed = Complex::new(0.4, 0.9);
+    let mut roots: Vec<Complex<f64>> = (0..degree).map(|k| seed.powi(k as i32)).collect();
+
+    for _ in 0..1000 {
+        let mut moved: f64 = 0.0;
+        for k in 0..degree {
+            let denom = (0..degree)
+                .filter(|&j| j != k)
+                .fold(Complex::new(1.0, 0.0), |acc, j| acc * (roots[k] - roots[j]));
+            let step = horner(&monic, roots[k]) / denom;
+            roots[k] -= step;
+            moved = moved.max(step.norm());
+        }
+        if moved < 1e-14 {
+            break;
+        }
+    }
+    roots
+}
+
+// calculate the basin of each point in a viewport at the given precision
+pub fn render(
+    precision: Precision,
+    newton: &Newton,
+    min: Complex<f64>,
+    max: Complex<f64>,
+    cols: usize,
+    rows: usize,
+    threads: usize,
+) -> Vec<Basin> {
+    Grid::new(newton, min, max, cols, rows, threads).render(precision)
+}
+
+// one character per sample: the ramp picks out each root's basin, and the
+// colour (if enabled) adds how quickly it got there
+pub fn render_ascii(
+    newton: &Newton,
+    grid: &[Basin],
+    cols: usize,
+    colors: &Colors,
+    ramp: &Ramp,
+) -> Vec<String> {
+    let roots = newton.roots.len();
+    grid.chunks(cols)
+        .map(|row| {
+            let mut line = String::with_capacity(cols);
+            let mut last = None;
+            for &basin in row {
+                let rgb = newton.rgb(basin);
+                if rgb != last {
+                    colors.set_fg(&mut line, rgb);
+                    last = rgb;
+                }
+                line.push(match basin {
+                    Some((root, _)) => ramp.value_char((root * 224 / roots) as u8),
+                    None => ramp.value_char(255),
+                });
+            }
+            if last.is_some() {
+                colors.reset(&mut line);
+            }
+            line
+        })
+        .collect()
+}
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Float types the fractal engine can run with, selectable at runtime.
+
+use num::complex::Complex;
+use std::fmt;
+
+// floating-point precisions we support
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    Single,
+    Double,
+}
+
+impl Precision {
+    // default precision, configured based on CPU features
+    #[cfg(feature = "f32")]
+    pub const DEFAULT: Self = Precision::Single;
+    #[cfg(not(feature = "f32"))]
+    pub const DEFAULT: Self = Precision::Double;
+
+    pub const ALL: [Self; 2] = [Precision::Single, Precision::Double];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Precision::Single => "single",
+            Precision::Double => "double",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "single" | "f32" => Some(Precision::Single),
+            "double" | "f64" => Some(Precision::Double),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// float types usable by the engine, conversions are plain `as` casts
+pub trait Real: num::Float + fmt::Debug + Send + Sync {
+    fn from_f64(x: f64) -> Self;
+    fn from_usize(x: usize) -> Self;
+}
+
+impl Real for f32 {
+    fn from_f64(x: f64) -> Self {
+        x as f32
+    }
+
+    fn from_usize(x: usize) -> Self {
+        x as f32
+    }
+}
+
+impl Real for f64 {
+    fn from_f64(x: f64) -> Self {
+        x
+    }
+
+    fn from_usize(x: usize) -> Self {
+        x as f64
+    }
+}
+
+// convert a double-precision complex constant to the working precision
+pub fn complex<T: Real>(c: Complex<f64>) -> Complex<T> {
+    Complex::new(T::from_f64(c.re), T::from_f64(c.im))
+}
@@ -0,0 +1,142 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Golden-reference comparison of the default Mandelbrot viewport.
+// The reference grids were generated on x86_64 with
+// `float_test --dump-grid --precision <single|double>`. Any IEEE-754
+// conforming target must reproduce them exactly, so a single differing cell
+// means the float code is broken.
+
+use crate::fractal::{self, Ifs, Iter, MAX_ITER, VIEW_MAX, VIEW_MIN};
+use crate::precision::Precision;
+use std::fmt::Write;
+
+// fixed reference grid size, independent of the terminal
+pub const COLS: usize = 64;
+pub const ROWS: usize = 32;
+
+// iteration counts are hashed as u64, so the u64 feature doesn't change these
+const GRID_F32: &str = include_str!("reference/mandel_f32.txt");
+const HASH_F32: u64 = 0x5e32_ef50_75eb_107d;
+const GRID_F64: &str = include_str!("reference/mandel_f64.txt");
+const HASH_F64: u64 = 0x2de3_15b7_1f4f_1fbd;
+
+// embedded grid and hash for a precision
+fn golden(precision: Precision) -> (&'static str, u64) {
+    match precision {
+        Precision::Single => (GRID_F32, HASH_F32),
+        Precision::Double => (GRID_F64, HASH_F64),
+    }
+}
+
+// hash of the embedded grid for a precision
+pub fn expected_hash(precision: Precision) -> u64 {
+    golden(precision).1
+}
+
+// a cell that doesn't match the reference
+pub struct Diff {
+    pub row: usize,
+    pub col: usize,
+    pub expected: Iter,
+    pub actual: Iter,
+}
+
+impl Diff {
+    // signed difference between actual and expected iteration counts
+    pub fn delta(&self) -> i64 {
+        self.actual as i64 - self.expected as i64
+    }
+}
+
+// outcome of comparing a fresh render with the reference
+pub struct Comparison {
+    pub hash: u64,
+    pub expected_hash: u64,
+    pub diffs: Vec<Diff>,
+}
+
+impl Comparison {
+    pub fn passed(&self) -> bool {
+        self.hash == self.expected_hash && self.diffs.is_empty()
+    }
+}
+
+// 64-bit FNV-1a over the iteration counts
+pub fn hash(grid: &[Iter]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &count in grid {
+        // Iter is already u64 with the u64 feature
+        #[allow(clippy::unnecessary_cast)]
+        let count = count as u64;
+        for byte in count.to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+// render the reference viewport on this machine
+pub fn render(precision: Precision) -> Vec<Iter> {
+    let mandel = Ifs::new(MAX_ITER);
+    fractal::render(precision, &mandel, VIEW_MIN, VIEW_MAX, COLS, ROWS, 1)
+}
+
+// parse an embedded reference grid
+fn parse(grid: &str) -> Vec<Iter> {
+    grid.lines()
+        .filter(|line| !line.starts_with('#'))
+        .flat_map(str::split_whitespace)
+        .map(|count| count.parse().expect("corrupt reference grid"))
+        .collect()
+}
+
+// render and check against the reference, listing diverging cells on mismatch
+pub fn compare(precision: Precision) -> Comparison {
+    let (expected, expected_hash) = golden(precision);
+    let grid = render(precision);
+    let hash = hash(&grid);
+    let mut diffs = Vec::new();
+
+    if hash != expected_hash {
+        let expected = parse(expected);
+        for (i, (&actual, &expected)) in grid.iter().zip(&expected).enumerate() {
+            if actual != expected {
+                diffs.push(Diff {
+                    row: i / COLS,
+                    col: i % COLS,
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+
+    Comparison {
+        hash,
+        expected_hash,
+        diffs,
+    }
+}
+
+// format a freshly rendered grid in the reference file layout
+pub fn dump(precision: Precision) -> String {
+    let grid = render(precision);
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "# {}x{} {} precision, hash {:#018x}",
+        COLS,
+        ROWS,
+        precision,
+        hash(&grid)
+    );
+    for line in grid.chunks(COLS) {
+        let line: Vec<String> = line.iter().map(|count| count.to_string()).collect();
+        let _ = writeln!(out, "{}", line.join(" "));
+    }
+    out
+}
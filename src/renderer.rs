@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Text renderers for iteration grids. The denser ones pack several samples
+// into each character cell, so the grid has to be computed at a multiple of
+// the output size (see Renderer::samples).
+
+use crate::ascii::{render_ascii, Ramp};
+use crate::color::{ColorMode, Colors, Rgb};
+use crate::mapping::Intensity;
+
+// ways of turning samples into characters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Renderer {
+    // one character from the ASCII ramp per sample
+    Ascii,
+    // upper half block with fg/bg colours, 1x2 samples per cell
+    HalfBlock,
+    // braille dot patterns, 2x4 samples per cell
+    Braille,
+}
+
+impl Renderer {
+    pub const ALL: [Self; 3] = [Renderer::Ascii, Renderer::HalfBlock, Renderer::Braille];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Renderer::Ascii => "ascii",
+            Renderer::HalfBlock => "halfblock",
+            Renderer::Braille => "braille",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|r| r.name() == name)
+    }
+
+    // samples per character cell, horizontally and vertically
+    pub fn samples(self) -> (usize, usize) {
+        match self {
+            Renderer::Ascii => (1, 1),
+            Renderer::HalfBlock => (1, 2),
+            Renderer::Braille => (2, 4),
+        }
+    }
+
+    // render a grid `width` samples wide into lines of text. the grid height
+    // should be a multiple of the vertical samples per cell. only the ASCII
+    // renderer uses the ramp
+    pub fn render(
+        self,
+        grid: &[Intensity],
+        width: usize,
+        colors: &Colors,
+        ramp: &Ramp,
+    ) -> Vec<String> {
+        match self {
+            Renderer::Ascii => render_ascii(grid, width, colors, ramp),
+            Renderer::HalfBlock => render_halfblock(grid, width, colors),
+            Renderer::Braille => render_braille(grid, width, colors),
+        }
+    }
+}
+
+// track the current colours so escapes are only written on change
+struct Pen {
+    fg: Option<Rgb>,
+    bg: Option<Rgb>,
+}
+
+impl Pen {
+    fn new() -> Self {
+        Self { fg: None, bg: None }
+    }
+
+    fn set(&mut self, out: &mut String, colors: &Colors, fg: Option<Rgb>, bg: Option<Rgb>) {
+        if fg == self.fg && bg == self.bg {
+            return;
+        }
+        // there's no escape to reset just one of them
+        if (fg.is_none() && self.fg.is_some()) || (bg.is_none() && self.bg.is_some()) {
+            colors.reset(out);
+            self.fg = None;
+            self.bg = None;
+        }
+        if let Some(rgb) = fg.filter(|_| fg != self.fg) {
+            colors.set_fg(out, Some(rgb));
+        }
+        if let Some(rgb) = bg.filter(|_| bg != self.bg) {
+            colors.set_bg(out, rgb);
+        }
+        self.fg = fg;
+        self.bg = bg;
+    }
+
+    fn finish(&mut self, out: &mut String, colors: &Colors) {
+        self.set(out, colors, None, None);
+    }
+}
+
+// two samples stacked in each cell. in colour, the top one is the foreground
+// of an upper half block and the bottom one the background, with the inside
+// of the set drawn black. without colour, the set is drawn in solid blocks
+pub fn render_halfblock(grid: &[Intensity], width: usize, colors: &Colors) -> Vec<String> {
+    grid.chunks(width * 2)
+        .map(|pair| {
+            let (top, bottom) = pair.split_at(width.min(pair.len()));
+            let mut line = String::with_capacity(width);
+            let mut pen = Pen::new();
+
+            for (col, &t) in top.iter().enumerate() {
+                let b = bottom.get(col).copied();
+                if colors.mode == ColorMode::Plain {
+                    line.push(match (t.is_none(), b == Some(None)) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    });
+                    continue;
+                }
+                let black = (0, 0, 0);
+                let fg = colors.intensity_rgb(t).unwrap_or(black);
+                let bg = b.map_or(black, |b| colors.intensity_rgb(b).unwrap_or(black));
+                pen.set(&mut line, colors, Some(fg), Some(bg));
+                line.push('▀');
+            }
+            pen.finish(&mut line, colors);
+            line
+        })
+        .collect()
+}
+
+// braille dot bits for each sample in a 2x4 cell, by row then column
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+// eight samples per cell, with a dot for each one inside the set. in colour,
+// the cell background is the colour of its slowest escaping sample
+pub fn render_braille(grid: &[Intensity], width: usize, colors: &Colors) -> Vec<String> {
+    let cols = width.div_ceil(2);
+
+    grid.chunks(width * 4)
+        .map(|band| {
+            let mut line = String::with_capacity(cols);
+            let mut pen = Pen::new();
+
+            for col in 0..cols {
+                let mut bits = 0;
+                let mut slowest: Option<f64> = None;
+                for (dy, dots) in BRAILLE_DOTS.iter().enumerate() {
+                    for (dx, &dot) in dots.iter().enumerate() {
+                        let x = col * 2 + dx;
+                        match band.get(dy * width + x) {
+                            Some(&None) if x < width => bits |= dot,
+                            Some(&Some(t)) if x < width => {
+                                if slowest.is_none_or(|s| t > s) {
+                                    slowest = Some(t);
+                                }
+                            }
+                            _ => continue,
+                        }
+                    }
+                }
+                let bg = colors.intensity_rgb(slowest);
+                pen.set(&mut line, colors, None, bg);
+                line.push(char::from_u32(0x2800 + bits).unwrap_or(' '));
+            }
+            pen.finish(&mut line, colors);
+            line
+        })
+        .collect()
+}
@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Machine-readable JSON report of build info and check results, for scripts
+// that would otherwise have to scrape the text output.
+
+use float_test::bench::Bench;
+use float_test::reference::Diff;
+use float_test::selftest::{Check, Run};
+use float_test::{build, Precision, ITER_BITS};
+use std::fmt::Write;
+
+// quote and escape a string for JSON
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// write a JSON array, one item per line, at the given indent
+fn array<T>(out: &mut String, indent: usize, items: &[T], item: impl Fn(&mut String, &T)) {
+    out.push('[');
+    for (i, it) in items.iter().enumerate() {
+        let _ = write!(
+            out,
+            "{}\n{:indent$}",
+            if i == 0 { "" } else { "," },
+            "",
+            indent = indent + 2
+        );
+        item(out, it);
+    }
+    if !items.is_empty() {
+        let _ = write!(out, "\n{:indent$}", "", indent = indent);
+    }
+    out.push(']');
+}
+
+fn check(out: &mut String, check: &Check) {
+    let _ = write!(
+        out,
+        "{{ \"name\": {}, \"passed\": {} }}",
+        quote(check.name),
+        check.passed
+    );
+}
+
+fn diff(out: &mut String, diff: &Diff) {
+    let _ = write!(
+        out,
+        "{{ \"row\": {}, \"col\": {}, \"expected\": {}, \"actual\": {} }}",
+        diff.row, diff.col, diff.expected, diff.actual
+    );
+}
+
+fn run(out: &mut String, run: &Run) {
+    out.push_str("{\n");
+    let _ = writeln!(out, "      \"precision\": {},", quote(run.precision.name()));
+    out.push_str("      \"checks\": ");
+    array(out, 6, &run.checks, check);
+    out.push_str(",\n      \"reference\": {\n");
+    let _ = writeln!(out, "        \"hash\": \"{:#018x}\",", run.reference.hash);
+    let _ = writeln!(
+        out,
+        "        \"expected_hash\": \"{:#018x}\",",
+        run.reference.expected_hash
+    );
+    let _ = writeln!(out, "        \"passed\": {},", run.reference.passed());
+    out.push_str("        \"diffs\": ");
+    array(out, 8, &run.reference.diffs, diff);
+    out.push_str("\n      },\n");
+    let _ = writeln!(out, "      \"passed\": {}", run.passed());
+    out.push_str("    }");
+}
+
+fn bench(out: &mut String, bench: &Bench) {
+    out.push_str("{\n");
+    let _ = writeln!(
+        out,
+        "      \"precision\": {},",
+        quote(bench.precision.name())
+    );
+    let _ = writeln!(out, "      \"threads\": {},", bench.threads);
+    let _ = writeln!(out, "      \"rounds\": {},", bench.rounds);
+    let _ = writeln!(out, "      \"pixels\": {},", bench.pixels);
+    let _ = writeln!(out, "      \"iterations\": {},", bench.iterations);
+    let _ = writeln!(out, "      \"seconds\": {},", bench.elapsed.as_secs_f64());
+    let _ = writeln!(
+        out,
+        "      \"iterations_per_sec\": {:.0},",
+        bench.iterations_per_sec()
+    );
+    let _ = writeln!(out, "      \"ns_per_pixel\": {:.1}", bench.ns_per_pixel());
+    out.push_str("    }");
+}
+
+// build the full report. benchmarks are only listed when they were run
+pub fn json(runs: &[Run], cross: &[Check], benches: &[Bench], passed: bool) -> String {
+    let mut out = String::new();
+
+    out.push_str("{\n");
+    let _ = writeln!(out, "  \"version\": {},", quote(build::PKG_VERSION));
+    let _ = writeln!(out, "  \"target\": {},", quote(build::BUILD_TARGET));
+    let _ = writeln!(out, "  \"rust_version\": {},", quote(build::RUST_VERSION));
+    let _ = writeln!(out, "  \"channel\": {},", quote(build::BUILD_RUST_CHANNEL));
+    let _ = writeln!(out, "  \"build_time\": {},", quote(build::BUILD_TIME_2822));
+    let _ = writeln!(
+        out,
+        "  \"default_precision\": {},",
+        quote(Precision::DEFAULT.name())
+    );
+    let _ = writeln!(out, "  \"iter_bits\": {},", ITER_BITS);
+    out.push_str("  \"runs\": ");
+    array(&mut out, 2, runs, run);
+    out.push_str(",\n  \"cross_checks\": ");
+    array(&mut out, 2, cross, check);
+    if !benches.is_empty() {
+        out.push_str(",\n  \"bench\": ");
+        array(&mut out, 2, benches, bench);
+    }
+    let _ = writeln!(out, ",\n  \"passed\": {}", passed);
+    out.push('}');
+    out
+}
@@ -0,0 +1,328 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// IEEE-754 conformance checks, instantiated once per supported precision.
+// Every operand goes through black_box() so the compiler can't fold the
+// arithmetic at build time on the host; we want the target FPU (or soft-float
+// routines) to do the work.
+
+use crate::precision::Precision;
+use crate::reference::{self, Comparison};
+use std::hint::black_box;
+
+// result of a single check
+pub struct Check {
+    pub name: &'static str,
+    pub passed: bool,
+}
+
+// a named check function
+type CheckFn = (&'static str, fn() -> bool);
+
+// the same battery of checks works for any IEEE-754 binary format
+macro_rules! ieee_checks {
+    ($module:ident, $float:ty) => {
+        mod $module {
+            use super::CheckFn;
+            use std::hint::black_box;
+            use std::num::FpCategory;
+
+            type Float = $float;
+
+            // shorthand for an opaque operand
+            fn v(x: Float) -> Float {
+                black_box(x)
+            }
+
+            // exact arithmetic identities
+            fn exact_add() -> bool {
+                v(1.5) + v(2.25) == 3.75
+            }
+
+            fn exact_sub() -> bool {
+                v(10.0) - v(0.125) == 9.875
+            }
+
+            fn exact_mul() -> bool {
+                v(3.0) * v(-7.0) == -21.0
+            }
+
+            fn exact_div() -> bool {
+                v(1.0) / v(4.0) == 0.25 && v(-9.0) / v(3.0) == -3.0
+            }
+
+            fn exact_sqrt() -> bool {
+                v(2.25).sqrt() == 1.5 && v(1.0).sqrt() == 1.0
+            }
+
+            fn exact_roundtrip() -> bool {
+                let x = v(1234.5678);
+                (x * v(2.0)) / v(2.0) == x
+            }
+
+            // round-to-nearest, ties-to-even
+            fn round_tie_down() -> bool {
+                // 1 + eps/2 is exactly halfway between 1 and 1 + eps, 1 has the even mantissa
+                v(1.0) + v(Float::EPSILON / 2.0) == 1.0
+            }
+
+            fn round_tie_up() -> bool {
+                // 1 + eps is odd, so the tie goes up to 1 + 2*eps
+                let one_eps = v(1.0 + Float::EPSILON);
+                one_eps + v(Float::EPSILON / 2.0) == 1.0 + 2.0 * Float::EPSILON
+            }
+
+            fn round_nearest() -> bool {
+                // just above the halfway point must round up, just below must round down
+                let up = v(1.0) + v(Float::EPSILON * 0.75);
+                let down = v(1.0) + v(Float::EPSILON * 0.25);
+                up == 1.0 + Float::EPSILON && down == 1.0
+            }
+
+            // signed zero
+            fn zero_equality() -> bool {
+                v(-0.0) == v(0.0)
+            }
+
+            fn zero_sign() -> bool {
+                let nz = v(0.0) * v(-1.0);
+                nz == 0.0 && nz.is_sign_negative()
+            }
+
+            fn zero_sum() -> bool {
+                // -0 + +0 is +0 in round-to-nearest
+                (v(-0.0) + v(0.0)).is_sign_positive()
+            }
+
+            fn zero_div() -> bool {
+                v(1.0) / v(-0.0) == Float::NEG_INFINITY && v(1.0) / v(0.0) == Float::INFINITY
+            }
+
+            // infinities
+            fn inf_arith() -> bool {
+                let inf = v(Float::INFINITY);
+                inf + v(1.0) == Float::INFINITY && -inf * v(2.0) == Float::NEG_INFINITY
+            }
+
+            fn inf_reciprocal() -> bool {
+                let r = v(1.0) / v(Float::INFINITY);
+                r == 0.0 && r.is_sign_positive()
+            }
+
+            fn inf_cancel() -> bool {
+                (v(Float::INFINITY) - v(Float::INFINITY)).is_nan()
+            }
+
+            // NaN propagation
+            fn nan_compare() -> bool {
+                let nan = v(Float::NAN);
+                let other = v(Float::NAN);
+                nan != other
+                    && nan.partial_cmp(&v(0.0)).is_none()
+                    && nan.partial_cmp(&nan).is_none()
+            }
+
+            fn nan_propagate() -> bool {
+                let nan = v(Float::NAN);
+                (nan + v(1.0)).is_nan() && (nan * v(0.0)).is_nan() && (v(2.0) / nan).is_nan()
+            }
+
+            fn nan_generate() -> bool {
+                (v(0.0) / v(0.0)).is_nan()
+                    && v(-1.0).sqrt().is_nan()
+                    && (v(0.0) * v(Float::INFINITY)).is_nan()
+            }
+
+            // subnormals
+            fn subnormal_produce() -> bool {
+                let s = v(Float::MIN_POSITIVE) / v(2.0);
+                s > 0.0 && s.classify() == FpCategory::Subnormal
+            }
+
+            fn subnormal_exact() -> bool {
+                let s = v(Float::MIN_POSITIVE) / v(4.0);
+                s * v(4.0) == Float::MIN_POSITIVE
+            }
+
+            fn subnormal_smallest() -> bool {
+                let tiny = v(Float::from_bits(1));
+                tiny > 0.0
+                    && tiny.classify() == FpCategory::Subnormal
+                    && tiny + tiny == Float::from_bits(2)
+            }
+
+            // overflow and underflow
+            fn overflow() -> bool {
+                let max = v(Float::MAX);
+                max + max == Float::INFINITY && max * v(-2.0) == Float::NEG_INFINITY
+            }
+
+            fn overflow_boundary() -> bool {
+                // MAX + 1 is far below half an ulp, so it must round back to MAX
+                v(Float::MAX) + v(1.0) == Float::MAX
+            }
+
+            fn underflow() -> bool {
+                // halfway between zero and the smallest subnormal ties to even (zero)
+                let z = v(Float::from_bits(1)) * v(0.5);
+                z == 0.0 && z.is_sign_positive()
+            }
+
+            fn underflow_sign() -> bool {
+                let z = v(-Float::MIN_POSITIVE) * v(Float::MIN_POSITIVE);
+                z == 0.0 && z.is_sign_negative()
+            }
+
+            pub const CHECKS: &[CheckFn] = &[
+                ("exact addition", exact_add),
+                ("exact subtraction", exact_sub),
+                ("exact multiplication", exact_mul),
+                ("exact division", exact_div),
+                ("exact square root", exact_sqrt),
+                ("multiply/divide round trip", exact_roundtrip),
+                ("round ties to even (down)", round_tie_down),
+                ("round ties to even (up)", round_tie_up),
+                ("round to nearest", round_nearest),
+                ("signed zero equality", zero_equality),
+                ("signed zero from multiply", zero_sign),
+                ("signed zero sum", zero_sum),
+                ("division by signed zero", zero_div),
+                ("infinity arithmetic", inf_arith),
+                ("reciprocal of infinity", inf_reciprocal),
+                ("infinity cancellation", inf_cancel),
+                ("NaN comparisons", nan_compare),
+                ("NaN propagation", nan_propagate),
+                ("NaN generation", nan_generate),
+                ("subnormal production", subnormal_produce),
+                ("subnormal exact scaling", subnormal_exact),
+                ("smallest subnormal", subnormal_smallest),
+                ("overflow to infinity", overflow),
+                ("overflow rounding", overflow_boundary),
+                ("underflow to zero", underflow),
+                ("underflow sign", underflow_sign),
+            ];
+        }
+    };
+}
+
+ieee_checks!(single, f32);
+ieee_checks!(double, f64);
+
+// run a list of checks
+fn run_checks(checks: &[CheckFn]) -> Vec<Check> {
+    checks
+        .iter()
+        .map(|&(name, check)| Check {
+            name,
+            passed: check(),
+        })
+        .collect()
+}
+
+// run every IEEE-754 check for one precision
+pub fn run(precision: Precision) -> Vec<Check> {
+    match precision {
+        Precision::Single => run_checks(single::CHECKS),
+        Precision::Double => run_checks(double::CHECKS),
+    }
+}
+
+// deterministic single-precision operands spread over a wide exponent range,
+// staying clear of overflow and subnormals so both precisions round normally
+fn operands() -> impl Iterator<Item = (f32, f32)> {
+    let mut state: u32 = 0x2545_f491;
+    let mut next = move || {
+        // xorshift32
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        let sign = state & 0x8000_0000;
+        let exponent = 127 - 20 + (state >> 23) % 41;
+        f32::from_bits(sign | exponent << 23 | (state & 0x007f_ffff))
+    };
+    (0..1024).map(move |_| (next(), next()))
+}
+
+// f64 has more than twice the significand bits of f32, so doing an operation
+// in double precision and rounding the result to single must give exactly the
+// single-precision result. this catches one precision being miscomputed even
+// when it happens to pass its own checks
+fn cross_add() -> bool {
+    operands().all(|(a, b)| {
+        black_box(a) + black_box(b) == (black_box(a as f64) + black_box(b as f64)) as f32
+    })
+}
+
+fn cross_sub() -> bool {
+    operands().all(|(a, b)| {
+        black_box(a) - black_box(b) == (black_box(a as f64) - black_box(b as f64)) as f32
+    })
+}
+
+fn cross_mul() -> bool {
+    operands().all(|(a, b)| {
+        black_box(a) * black_box(b) == (black_box(a as f64) * black_box(b as f64)) as f32
+    })
+}
+
+fn cross_div() -> bool {
+    operands().all(|(a, b)| {
+        black_box(a) / black_box(b) == (black_box(a as f64) / black_box(b as f64)) as f32
+    })
+}
+
+fn cross_sqrt() -> bool {
+    operands().all(|(a, _)| black_box(a.abs()).sqrt() == black_box(a.abs() as f64).sqrt() as f32)
+}
+
+fn cross_widen() -> bool {
+    operands().all(|(a, _)| black_box(a as f64) as f32 == a)
+}
+
+fn cross_narrow_ties() -> bool {
+    // exactly halfway between two singles, must round to the even one
+    let ulp = f32::EPSILON as f64;
+    black_box(1.0 + ulp / 2.0) as f32 == 1.0
+        && black_box(1.0 + ulp * 1.5) as f32 == 1.0 + 2.0 * f32::EPSILON
+}
+
+const CROSS_CHECKS: &[CheckFn] = &[
+    ("cross-precision addition", cross_add),
+    ("cross-precision subtraction", cross_sub),
+    ("cross-precision multiplication", cross_mul),
+    ("cross-precision division", cross_div),
+    ("cross-precision square root", cross_sqrt),
+    ("single to double round trip", cross_widen),
+    ("double to single ties to even", cross_narrow_ties),
+];
+
+// run the checks comparing single and double precision against each other
+pub fn cross() -> Vec<Check> {
+    run_checks(CROSS_CHECKS)
+}
+
+// all results for one precision
+pub struct Run {
+    pub precision: Precision,
+    pub checks: Vec<Check>,
+    pub reference: Comparison,
+}
+
+impl Run {
+    pub fn new(precision: Precision) -> Self {
+        Self {
+            precision,
+            checks: run(precision),
+            reference: reference::compare(precision),
+        }
+    }
+
+    pub fn failed(&self) -> usize {
+        self.checks.iter().filter(|c| !c.passed).count()
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failed() == 0 && self.reference.passed()
+    }
+}
@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Mariani-Silver subdivision: the border of a rectangle is evaluated first,
+// and if every point on it is inside the set, the rest of it is filled in as
+// inside without being iterated. otherwise the rectangle is split into
+// quarters, which are tried in turn. this relies on the set having no holes,
+// as the mandelbrot set has none, so the command line only allows it for that
+// set. escaping points are always iterated, since the whole set can fit inside
+// a border that escapes at one speed, and the time goes inside the set anyway,
+// where every point runs to the iteration limit.
+//
+// the grid matches a brute-force render unless a filament of escaping points
+// narrower than a cell slips between two samples on a border and reaches a
+// sample inside, which is then filled over. that's rare, and shows up as the
+// odd missing pixel in large renders.
+
+use crate::fractal::{in_parallel, sample_point, Escape, Grid, Ifs, Iter, Iters, Method, Smooth};
+use crate::precision::{Precision, Real};
+use num::complex::Complex;
+
+// rectangles this narrow or short are evaluated point by point
+const MIN_SIZE: usize = 4;
+
+// cells evaluated per job, as the cells wanted in a round are shared out
+const BATCH: usize = 256;
+
+// corners of a rectangle of cells, inclusive
+#[derive(Clone, Copy)]
+struct Rect {
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize,
+}
+
+impl Rect {
+    fn new(x0: usize, y0: usize, x1: usize, y1: usize) -> Self {
+        Self { x0, y0, x1, y1 }
+    }
+
+    fn is_small(self) -> bool {
+        self.x1 - self.x0 < MIN_SIZE || self.y1 - self.y0 < MIN_SIZE
+    }
+
+    fn border(self) -> impl Iterator<Item = (usize, usize)> {
+        let Rect { x0, y0, x1, y1 } = self;
+        let rows = (x0..=x1).flat_map(move |x| [(x, y0), (x, y1)]);
+        let cols = (y0 + 1..y1).flat_map(move |y| [(x0, y), (x1, y)]);
+        rows.chain(cols)
+    }
+
+    fn cells(self) -> impl Iterator<Item = (usize, usize)> {
+        let Rect { x0, y0, x1, y1 } = self;
+        (y0..=y1).flat_map(move |y| (x0..=x1).map(move |x| (x, y)))
+    }
+
+    // quarters sharing the middle row and column, so the split lines are
+    // evaluated once and serve as the border on both sides
+    fn quarters(self) -> [Rect; 4] {
+        let Rect { x0, y0, x1, y1 } = self;
+        let (xm, ym) = ((x0 + x1) / 2, (y0 + y1) / 2);
+        [
+            Rect::new(x0, y0, xm, ym),
+            Rect::new(xm, y0, x1, ym),
+            Rect::new(x0, ym, xm, y1),
+            Rect::new(xm, ym, x1, y1),
+        ]
+    }
+}
+
+// fill in every cell of a grid, evaluating as few as possible. this goes a
+// round at a time: the cells every rectangle still pending needs are shared
+// out between threads, then each rectangle is filled in or split for the next
+// round. which cells are evaluated only depends on the results, so the grid
+// is the same however many threads there are
+fn subdivide<R: Copy + PartialEq + Send>(
+    cols: usize,
+    rows: usize,
+    threads: usize,
+    eval: impl Fn(usize, usize) -> R + Sync,
+    inside: impl Fn(R) -> bool,
+) -> Vec<R> {
+    let mut cells: Vec<Option<R>> = vec![None; cols * rows];
+    let mut pending = vec![Rect::new(0, 0, cols - 1, rows - 1)];
+    while !pending.is_empty() {
+        // the border of a rectangle big enough to be filled in, or all of one
+        // that isn't, less what's already known
+        let mut wanted: Vec<usize> = Vec::new();
+        for &rect in &pending {
+            if rect.is_small() {
+                wanted.extend(rect.cells().map(|(x, y)| y * cols + x));
+            } else {
+                wanted.extend(rect.border().map(|(x, y)| y * cols + x));
+            }
+        }
+        wanted.retain(|&cell| cells[cell].is_none());
+        wanted.sort_unstable();
+        wanted.dedup();
+
+        let values = in_parallel(wanted.len().div_ceil(BATCH), threads, |batch| {
+            wanted[batch * BATCH..]
+                .iter()
+                .take(BATCH)
+                .map(|&cell| eval(cell % cols, cell / cols))
+                .collect()
+        });
+        for (cell, value) in wanted.into_iter().zip(values) {
+            cells[cell] = Some(value);
+        }
+
+        let at = |x: usize, y: usize| cells[y * cols + x].expect("border is evaluated");
+        let mut fills = Vec::new();
+        let mut next = Vec::new();
+        for rect in pending.into_iter().filter(|rect| !rect.is_small()) {
+            let first = at(rect.x0, rect.y0);
+            if inside(first) && rect.border().all(|(x, y)| at(x, y) == first) {
+                fills.push((rect, first));
+            } else {
+                next.extend(rect.quarters());
+            }
+        }
+        // rectangles only share their borders, which are already evaluated
+        for (rect, value) in fills {
+            for (x, y) in rect.cells() {
+                cells[y * cols + x].get_or_insert(value);
+            }
+        }
+        pending = next;
+    }
+    cells
+        .into_iter()
+        .map(|cell| cell.expect("every cell is evaluated or filled"))
+        .collect()
+}
+
+// evaluate a function over a viewport like sample_grid, but by subdivision
+pub fn subdivide_grid<T: Real, R: Copy + PartialEq + Send>(
+    min: Complex<T>,
+    max: Complex<T>,
+    cols: usize,
+    rows: usize,
+    threads: usize,
+    f: impl Fn(Complex<T>) -> R + Sync,
+    inside: impl Fn(R) -> bool,
+) -> Vec<R> {
+    if cols == 0 || rows == 0 {
+        return Vec::new();
+    }
+    subdivide(
+        cols,
+        rows,
+        threads,
+        |col, row| f(sample_point(min, max, cols, rows, col, row)),
+        inside,
+    )
+}
+
+// calculate iteration counts for a viewport at the given precision
+pub fn render(
+    precision: Precision,
+    mandel: &Ifs,
+    min: Complex<f64>,
+    max: Complex<f64>,
+    cols: usize,
+    rows: usize,
+    threads: usize,
+) -> Vec<Iter> {
+    Grid::new(&Iters(mandel), min, max, cols, rows, threads)
+        .with_method(Method::Subdivide)
+        .render(precision)
+}
+
+// calculate smooth iteration counts for a viewport at the given precision.
+// fractional counts are rarely equal, so this mostly saves time inside the set
+pub fn render_smooth(
+    precision: Precision,
+    mandel: &Ifs,
+    min: Complex<f64>,
+    max: Complex<f64>,
+    cols: usize,
+    rows: usize,
+    threads: usize,
+) -> Vec<Escape> {
+    Grid::new(&Smooth(mandel), min, max, cols, rows, threads)
+        .with_method(Method::Subdivide)
+        .render(precision)
+}
@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Tests for colour quantisation and coloured ASCII output.
+
+use float_test::color::{nearest_16, nearest_256};
+use float_test::{render_ascii, ColorMode, Colors, Palette, Ramp};
+
+#[test]
+fn palette_endpoints() {
+    assert_eq!(Palette::Grey.rgb(0.0), (32, 32, 32));
+    assert_eq!(Palette::Grey.rgb(1.0), (255, 255, 255));
+    assert_eq!(Palette::Fire.rgb(2.0), (255, 255, 255));
+    for palette in Palette::ALL {
+        assert_eq!(Palette::from_name(palette.name()), Some(palette));
+    }
+}
+
+#[test]
+fn quantisation() {
+    assert_eq!(nearest_16((0, 0, 0)), 0);
+    assert_eq!(nearest_16((250, 10, 10)), 9);
+    assert_eq!(nearest_256((255, 0, 0)), 196);
+    assert_eq!(nearest_256((0, 0, 0)), 16);
+    assert_eq!(nearest_256((128, 128, 128)), 244);
+}
+
+#[test]
+fn plain_output_has_no_escapes() {
+    let grid = [None, Some(1.0), Some(0.15), Some(0.0)];
+    let lines = render_ascii(&grid, 2, &Colors::PLAIN, &Ramp::default());
+
+    assert_eq!(lines, ["@@", ". "]);
+}
+
+#[test]
+fn colour_changes_only() {
+    let colors = Colors {
+        mode: ColorMode::Ansi16,
+        palette: Palette::Grey,
+    };
+    let lines = render_ascii(
+        &[None, None, Some(1.0), Some(1.0), None],
+        5,
+        &colors,
+        &Ramp::default(),
+    );
+
+    assert_eq!(lines[0].matches('\x1b').count(), 2);
+    assert!(lines[0].ends_with('@'));
+}
@@ -0,0 +1,327 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Tests against the library API.
+
+use float_test::{bench, reference, selftest};
+use float_test::{julia_preset, render, render_smooth, val_to_char, Formula, Ifs, Precision};
+use float_test::{Shortcuts, Viewport, MAX_ITER};
+use num::complex::Complex;
+
+#[test]
+fn points_inside_never_escape() {
+    let mandel = Ifs::new(MAX_ITER);
+
+    assert_eq!(mandel.iter(Complex::new(0.0f64, 0.0)), 0);
+    assert_eq!(mandel.iter(Complex::new(-1.0f32, 0.0)), 0);
+}
+
+#[test]
+fn points_outside_escape_immediately() {
+    let mandel = Ifs::new(MAX_ITER);
+
+    assert_eq!(mandel.iter(Complex::new(3.0f64, 0.0)), MAX_ITER);
+    assert_eq!(mandel.iter(Complex::new(0.0f32, -2.5)), MAX_ITER);
+}
+
+#[test]
+fn julia_of_zero_is_the_unit_disc() {
+    let julia = Ifs::julia(MAX_ITER, Complex::new(0.0, 0.0));
+
+    assert_eq!(julia.iter(Complex::new(0.0f64, 0.9)), 0);
+    assert_eq!(julia.iter(Complex::new(-0.5f32, 0.5)), 0);
+    assert_ne!(julia.iter(Complex::new(0.0f64, 1.1)), 0);
+    assert_eq!(julia.iter(Complex::new(2.5f32, 0.0)), MAX_ITER);
+}
+
+#[test]
+fn julia_presets() {
+    assert_eq!(julia_preset("dendrite"), Some(Complex::new(0.0, 1.0)));
+    assert_eq!(julia_preset("cauliflower"), None);
+
+    // the mandelbrot set is the map of connected julia sets, so a preset
+    // inside it should keep the origin bounded
+    let rabbit = Ifs::julia(MAX_ITER, julia_preset("rabbit").unwrap());
+    assert_eq!(rabbit.iter(Complex::new(0.0f64, 0.0)), 0);
+}
+
+#[test]
+fn formula_names() {
+    for formula in Formula::ALL {
+        assert_eq!(Formula::from_name(&formula.to_string()), Some(formula));
+    }
+    assert_eq!(
+        Formula::from_name("multibrot:4.0"),
+        Some(Formula::Multibrot(4))
+    );
+    assert_eq!(Formula::from_name("multibrot:1"), None);
+    assert_eq!(Formula::from_name("multibrot:nan"), None);
+    assert_eq!(Formula::from_name("buddhabrot"), None);
+}
+
+#[test]
+fn formulas_agree_where_they_should() {
+    let view = Viewport::default();
+    let grid = |formula| {
+        let fractal = Ifs::new(64).with_formula(formula);
+        render(Precision::Double, &fractal, view.min, view.max, 32, 16, 1)
+    };
+
+    // powi(2) is a single multiplication, so must match exactly
+    assert_eq!(grid(Formula::Multibrot(2)), grid(Formula::Mandelbrot));
+
+    // the tricorn is symmetric about the real axis like the mandelbrot set,
+    // but it and the burning ship differ from it elsewhere
+    let tricorn = Ifs::new(MAX_ITER).with_formula(Formula::Tricorn);
+    let c = Complex::new(-0.3f64, 0.7);
+    assert_eq!(tricorn.iter(c), tricorn.iter(c.conj()));
+    assert_ne!(grid(Formula::Tricorn), grid(Formula::Mandelbrot));
+    assert_ne!(grid(Formula::BurningShip), grid(Formula::Mandelbrot));
+}
+
+#[test]
+fn smooth_counts() {
+    let mandel = Ifs::new(MAX_ITER);
+
+    assert_eq!(mandel.smooth(Complex::new(-0.5f64, 0.0)), None);
+    assert_eq!(mandel.smooth(Complex::new(-1.0f32, 0.0)), None);
+
+    // points further out escape sooner, and never go negative
+    let counts: Vec<f64> = [0.5, 0.8, 1.0, 2.0, 1e6]
+        .iter()
+        .map(|&re| mandel.smooth(Complex::new(re, 0.5f64)).unwrap())
+        .collect();
+    assert!(counts.windows(2).all(|w| w[0] > w[1]), "{:?}", counts);
+    assert_eq!(counts[4], 0.0);
+}
+
+#[test]
+fn smooth_counts_are_continuous() {
+    // neighbouring points straddling a band edge of the integer count
+    let mandel = Ifs::new(MAX_ITER);
+    let view = Viewport::default();
+    let grid = render_smooth(Precision::Double, &mandel, view.min, view.max, 256, 1, 1);
+    let outside: Vec<f64> = grid[..32].iter().map(|e| e.unwrap()).collect();
+
+    assert!(
+        outside.windows(2).all(|w| (w[1] - w[0]).abs() < 0.5),
+        "{:?}",
+        outside
+    );
+    assert!(outside.iter().any(|e| e.fract() != 0.0));
+}
+
+#[test]
+fn ramp_ends() {
+    assert_eq!(val_to_char(0), '@');
+    assert_eq!(val_to_char(255), ' ');
+}
+
+#[test]
+fn centered_viewport() {
+    let default = Viewport::default();
+    let view = Viewport::centered(default.center(), 1.0);
+
+    assert!((view.min - default.min).norm() < 1e-12);
+    assert!((view.max - default.max).norm() < 1e-12);
+
+    let zoomed = Viewport::centered(Complex::new(0.0, 0.0), 4.0);
+    assert_eq!(zoomed.min, Complex::new(-0.25, -0.25));
+    assert_eq!(zoomed.max, Complex::new(0.25, 0.25));
+}
+
+#[test]
+fn panned_viewport() {
+    let view = Viewport::JULIA;
+    let panned = view.panned(0.25, -0.5);
+
+    assert!((panned.min - Complex::new(-0.9, -3.6)).norm() < 1e-12);
+    assert!((panned.max - Complex::new(2.7, 0.0)).norm() < 1e-12);
+    let back = panned.panned(-0.25, 0.5);
+    assert!((back.min - view.min).norm() < 1e-12);
+}
+
+#[test]
+fn zoomed_about_a_point() {
+    let view = Viewport::default();
+    let zoomed = view.zoomed_about(view.min, 2.0);
+
+    // the point stays put, and everything else halves its distance from it
+    assert_eq!(zoomed.min, view.min);
+    assert!((zoomed.max - Complex::new(-0.4, 0.0)).norm() < 1e-12);
+    let back = zoomed.zoomed_about(view.min, 0.5);
+    assert!((back.max - view.max).norm() < 1e-12);
+}
+
+#[test]
+fn render_dimensions() {
+    let view = Viewport::default();
+    let grid = render(
+        Precision::Single,
+        &Ifs::new(16),
+        view.min,
+        view.max,
+        7,
+        3,
+        1,
+    );
+
+    assert_eq!(grid.len(), 7 * 3);
+}
+
+#[test]
+fn threads_match_serial() {
+    let view = Viewport::default();
+    let mandel = Ifs::new(MAX_ITER);
+
+    for precision in Precision::ALL {
+        let serial = render(precision, &mandel, view.min, view.max, 64, 37, 1);
+        let smooth = render_smooth(precision, &mandel, view.min, view.max, 64, 37, 1);
+        for threads in [2, 3, 8, 64] {
+            let grid = render(precision, &mandel, view.min, view.max, 64, 37, threads);
+            assert_eq!(grid, serial, "{} precision, {} threads", precision, threads);
+
+            // compare the bits, so a NaN would still have to match
+            let bits = |grid: Vec<Option<f64>>| -> Vec<Option<u64>> {
+                grid.into_iter().map(|e| e.map(f64::to_bits)).collect()
+            };
+            let grid = render_smooth(precision, &mandel, view.min, view.max, 64, 37, threads);
+            assert_eq!(bits(grid), bits(smooth.clone()));
+        }
+    }
+}
+
+#[test]
+fn shortcut_names() {
+    let bulbs = Shortcuts {
+        bulbs: true,
+        periodicity: false,
+    };
+    assert_eq!(Shortcuts::from_names("bulbs"), Some(bulbs));
+    assert_eq!(
+        Shortcuts::from_names("periodicity,bulbs"),
+        Some(Shortcuts::ALL)
+    );
+    assert_eq!(Shortcuts::from_names("all"), Some(Shortcuts::ALL));
+    assert_eq!(Shortcuts::from_names("none"), Some(Shortcuts::default()));
+    assert_eq!(Shortcuts::from_names("bulbs,"), None);
+}
+
+#[test]
+fn shortcuts_match_brute_force() {
+    let view = Viewport::default();
+    let rabbit = julia_preset("rabbit").unwrap();
+    for precision in Precision::ALL {
+        let brute = render(
+            precision,
+            &Ifs::new(MAX_ITER),
+            view.min,
+            view.max,
+            96,
+            48,
+            1,
+        );
+        let mandel = Ifs::new(MAX_ITER).with_shortcuts(Shortcuts::ALL);
+        let fast = render(precision, &mandel, view.min, view.max, 96, 48, 1);
+        assert_eq!(fast, brute, "{}", precision);
+
+        let saved = mandel.saved();
+        assert!(saved.bulbs > 0 && saved.periodicity > 0, "{:?}", saved);
+        let inside = brute.iter().filter(|&&v| v == 0).count();
+        assert!(saved.bulbs + saved.periodicity <= inside);
+
+        // the bulbs only hold for the mandelbrot set itself
+        let view = Viewport::JULIA;
+        let brute = render(
+            precision,
+            &Ifs::julia(MAX_ITER, rabbit),
+            view.min,
+            view.max,
+            96,
+            48,
+            1,
+        );
+        let julia = Ifs::julia(MAX_ITER, rabbit).with_shortcuts(Shortcuts::ALL);
+        let fast = render(precision, &julia, view.min, view.max, 96, 48, 1);
+        assert_eq!(fast, brute, "{}", precision);
+        assert_eq!(julia.saved().bulbs, 0);
+    }
+}
+
+#[test]
+fn cancelled_renders_stop_iterating() {
+    let view = Viewport::default();
+    let mandel = Ifs::new(1 << 20);
+    mandel.cancel();
+
+    assert!(mandel.is_cancelled());
+    let grid = render(Precision::Double, &mandel, view.min, view.max, 64, 32, 1);
+    assert!(grid.iter().all(|&v| v == 1 << 20));
+}
+
+#[test]
+fn iteration_steps() {
+    // inside the set, escaped at once, and after 7 steps
+    assert_eq!(bench::steps(&[0, 10, 3], 10), 17);
+
+    let mandel = Ifs::new(100);
+    let grid = [
+        mandel.iter(Complex::new(0.0f64, 0.0)),
+        mandel.iter(Complex::new(3.0f64, 0.0)),
+    ];
+    assert_eq!(bench::steps(&grid, 100), 100);
+}
+
+#[test]
+fn reference_grids_match() {
+    for precision in Precision::ALL {
+        let comparison = reference::compare(precision);
+        assert!(comparison.passed(), "{} precision differs", precision);
+    }
+}
+
+#[test]
+fn ieee_checks_pass() {
+    for precision in Precision::ALL {
+        for check in selftest::run(precision) {
+            assert!(check.passed, "{}: {}", precision, check.name);
+        }
+    }
+    for check in selftest::cross() {
+        assert!(check.passed, "{}", check.name);
+    }
+}
+
+#[test]
+fn precision_names() {
+    for precision in Precision::ALL {
+        assert_eq!(Precision::from_name(precision.name()), Some(precision));
+    }
+    assert_eq!(Precision::from_name("quad"), None);
+}
+
+#[test]
+fn per_core_consensus() {
+    use float_test::cores::{consensus, CoreRun};
+    use std::io;
+
+    let expected = reference::expected_hash(Precision::Double);
+    let run = |cpu, hash| CoreRun {
+        cpu,
+        hash: Ok(hash),
+    };
+
+    let runs = [run(0, 1), run(1, 1), run(2, 2), run(3, 1)];
+    assert_eq!(consensus(&runs, Precision::Double), Some(1));
+
+    // with two cores, the one matching the reference wins
+    let runs = [run(0, 7), run(1, expected)];
+    assert_eq!(consensus(&runs, Precision::Double), Some(expected));
+
+    let failed = CoreRun {
+        cpu: 0,
+        hash: Err(io::ErrorKind::Unsupported.into()),
+    };
+    assert_eq!(consensus(&[failed], Precision::Double), None);
+}
@@ -0,0 +1,226 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Runs the built binary under whatever feature set the tests were built with.
+// CI runs this once per combination of the f32 and u64 features.
+
+use std::process::{Command, Output};
+
+fn float_test(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_float_test"))
+        .args(args)
+        .output()
+        .expect("failed to run float_test")
+}
+
+fn expected_precision() -> &'static str {
+    if cfg!(feature = "f32") {
+        "single"
+    } else {
+        "double"
+    }
+}
+
+fn expected_iter_bits() -> u32 {
+    if cfg!(feature = "u64") {
+        64
+    } else {
+        32
+    }
+}
+
+#[test]
+fn selftest_passes() {
+    let out = float_test(&["--selftest"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+
+    assert!(out.status.success(), "selftest failed:\n{}", stdout);
+    assert!(!stdout.contains("FAIL"), "selftest failed:\n{}", stdout);
+    assert!(stdout.contains("PASS reference grid"));
+}
+
+#[test]
+fn banner_reports_features() {
+    let out = float_test(&["--selftest"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let banner = format!(
+        "({} precision, {}-bit iterations)",
+        expected_precision(),
+        expected_iter_bits()
+    );
+
+    assert!(
+        stdout.contains(&banner),
+        "missing {:?} in:\n{}",
+        banner,
+        stdout
+    );
+}
+
+#[test]
+fn json_report_reports_features() {
+    let out = float_test(&["--report", "json"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+
+    assert!(out.status.success());
+    assert!(stdout.starts_with('{') && stdout.trim_end().ends_with('}'));
+    assert!(stdout.contains(&format!("\"precision\": \"{}\"", expected_precision())));
+    assert!(stdout.contains(&format!("\"iter_bits\": {}", expected_iter_bits())));
+    assert!(stdout.contains("\"passed\": true\n}"));
+}
+
+#[test]
+fn bad_arguments_exit_with_usage() {
+    let out = float_test(&["--no-such-flag"]);
+
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("usage:"));
+}
+
+#[test]
+fn selftest_both_precisions() {
+    let out = float_test(&["--selftest", "--precision", "both"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+
+    assert!(out.status.success(), "selftest failed:\n{}", stdout);
+    assert!(stdout.contains("single precision:"));
+    assert!(stdout.contains("double precision:"));
+    assert!(stdout.contains("PASS cross-precision addition"));
+}
+
+#[test]
+fn compare_reports_divergence() {
+    let out = float_test(&["--compare"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+
+    assert!(out.status.success());
+    assert!(stdout.contains("(single and double precision"));
+    assert!(stdout.contains("cells differ"));
+}
+
+#[test]
+fn explicit_size_and_viewport() {
+    let out = float_test(&[
+        "--width", "20", "--height", "5", "--min", "-1.4,-1", "--max", "0.6,1",
+    ]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert!(out.status.success());
+    assert!(lines[2].ends_with("will output 20x5 characters"));
+    assert_eq!(lines.len(), 3 + 5);
+    assert!(lines[3..].iter().all(|line| line.chars().count() == 20));
+}
+
+#[test]
+fn julia_mode() {
+    let out = float_test(&["--julia", "rabbit", "--width", "20", "--height", "5"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+
+    assert!(out.status.success());
+    assert!(stdout.contains("julia set for c = -0.123+0.745i"));
+
+    let out = float_test(&["--julia", "0.3", "--width", "20", "--height", "5"]);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn formula_selection() {
+    let out = float_test(&["--formula", "tricorn", "--width", "20", "--height", "5"]);
+
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("tricorn set"));
+
+    let out = float_test(&["--formula", "multibrot:0.5"]);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn newton_mode() {
+    let out = float_test(&["--newton", "1,0,0,-1", "--width", "20", "--height", "5"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+
+    assert!(out.status.success());
+    assert!(stdout.contains("newton fractal for z^3 - 1 (3 roots)"));
+
+    let out = float_test(&["--newton", "1,0,0,-1", "--julia", "rabbit"]);
+    assert_eq!(out.status.code(), Some(2));
+    let out = float_test(&["--newton", "1,x"]);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn per_core_check() {
+    let out = float_test(&["--per-core", "--precision", "both"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+
+    assert!(out.status.success(), "{}", stdout);
+    assert!(stdout.contains("PASS cpu   0"));
+    assert!(!stdout.contains("FAIL"));
+}
+
+#[test]
+fn shortcuts_are_reported() {
+    let size = ["--width", "40", "--height", "20", "--color", "none"];
+    let brute = float_test(&size);
+    let out = float_test(&[&size[..], &["--shortcuts", "all"]].concat());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+
+    assert!(out.status.success());
+    assert!(stdout.starts_with(&*String::from_utf8_lossy(&brute.stdout)));
+    assert!(stdout.contains("shortcuts settled"));
+}
+
+#[test]
+fn subdivided_render() {
+    let size = ["--width", "80", "--height", "40", "--color", "none"];
+    let brute = float_test(&size);
+    let out = float_test(&[&size[..], &["--subdivide"]].concat());
+
+    assert!(out.status.success());
+    assert_eq!(out.stdout, brute.stdout);
+
+    let out = float_test(&["--subdivide", "--julia", "rabbit"]);
+    assert_eq!(out.status.code(), Some(2));
+    let out = float_test(&["--subdivide", "--formula", "burning-ship"]);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn bench_in_json_report() {
+    let out = float_test(&["--bench", "--report", "json", "--threads", "2"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+
+    assert!(out.status.success(), "{}", stdout);
+    assert!(stdout.contains("\"bench\": ["));
+    assert_eq!(stdout.matches("\"iterations_per_sec\":").count(), 2);
+    assert!(stdout.contains("\"threads\": 2"));
+}
+
+#[test]
+fn conflicting_viewport_arguments() {
+    let out = float_test(&["--min", "-1,-1", "--max", "1,1", "--center", "0,0"]);
+
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn out_of_range_zoom() {
+    for zoom in ["inf", "1e-320", "1e300", "nan", "0"] {
+        let out = float_test(&["--center", "-0.5,0", "--zoom", zoom]);
+        assert_eq!(out.status.code(), Some(2), "--zoom {}", zoom);
+    }
+    let out = float_test(&["--min", "-1e308,-1", "--max", "1e308,1"]);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn explore_needs_the_terminal() {
+    let out = float_test(&["--explore", "--output", "out.png"]);
+    assert_eq!(out.status.code(), Some(2));
+
+    let out = float_test(&["--explore", "--newton", "1,0,0,-1"]);
+    assert_eq!(out.status.code(), Some(2));
+}
@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Tests for image export.
+
+use float_test::image::{adler32, crc32};
+use float_test::{escapes, write_image, Colors, ImageFormat, Mapping};
+use std::path::Path;
+
+fn export(format: ImageFormat) -> Vec<u8> {
+    let grid = Mapping::Log.intensities(&escapes(&[0, 1, 128, 256, 0, 0], 256), 256);
+    let mut out = Vec::new();
+    write_image(&mut out, format, &grid, 3, &Colors::PLAIN).unwrap();
+    out
+}
+
+#[test]
+fn checksums() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+}
+
+#[test]
+fn format_from_extension() {
+    assert_eq!(
+        ImageFormat::from_path(Path::new("a/b.PNG")),
+        Some(ImageFormat::Png)
+    );
+    assert_eq!(
+        ImageFormat::from_path(Path::new("x.pgm")),
+        Some(ImageFormat::Pgm)
+    );
+    assert_eq!(ImageFormat::from_path(Path::new("x.jpg")), None);
+    assert_eq!(ImageFormat::from_path(Path::new("ppm")), None);
+}
+
+#[test]
+fn netpbm() {
+    let pgm = export(ImageFormat::Pgm);
+    assert!(pgm.starts_with(b"P5\n3 2\n255\n"));
+    assert_eq!(pgm.len(), 11 + 6);
+    assert_eq!(pgm[11], 0);
+
+    let ppm = export(ImageFormat::Ppm);
+    assert!(ppm.starts_with(b"P6\n3 2\n255\n"));
+    assert_eq!(ppm.len(), 11 + 6 * 3);
+}
+
+#[test]
+fn png_structure() {
+    let png = export(ImageFormat::Png);
+
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(&png[16..24], &[0, 0, 0, 3, 0, 0, 0, 2]);
+    assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+
+    // each chunk's CRC covers its type and data
+    let mut pos = 8;
+    while pos < png.len() {
+        let len = u32::from_be_bytes(png[pos..pos + 4].try_into().unwrap()) as usize;
+        let crc = u32::from_be_bytes(png[pos + 8 + len..pos + 12 + len].try_into().unwrap());
+        assert_eq!(crc32(&png[pos + 4..pos + 8 + len]), crc);
+        pos += 12 + len;
+    }
+    assert_eq!(pos, png.len());
+}
@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Tests for mapping escape counts to intensities and characters.
+
+use float_test::{val_to_char, Mapping, Ramp};
+
+#[test]
+fn even_bands() {
+    let mut counts = [0usize; 10];
+    let ramp = "@%#*+=~:. ";
+    for value in 0..=255u8 {
+        let i = ramp.chars().position(|c| c == val_to_char(value)).unwrap();
+        counts[i] += 1;
+    }
+
+    assert!(counts.iter().all(|&n| n == 25 || n == 26), "{:?}", counts);
+}
+
+#[test]
+fn large_iteration_limits_dont_wrap() {
+    // with max_iter above 255 the old u8 cast sent these back round the ramp
+    let grid = [Some(10.0), Some(300.0), Some(700.0), Some(999.0), None];
+    let ramp = Ramp::default();
+    let chars: String = Mapping::Linear
+        .intensities(&grid, 1000)
+        .into_iter()
+        .map(|t| ramp.shade_char(t))
+        .collect();
+
+    assert_eq!(chars, " :*@@");
+}
+
+#[test]
+fn strategies() {
+    let grid = [Some(0.0), Some(1.0), Some(3.0), Some(15.0), None];
+
+    let linear = Mapping::Linear.intensities(&grid, 15);
+    assert_eq!(linear[..3], [Some(0.0), Some(1.0 / 15.0), Some(0.2)]);
+    assert_eq!(linear[3..], [Some(1.0), None]);
+
+    let log = Mapping::Log.intensities(&grid, 15);
+    assert_eq!(log[0], Some(0.0));
+    assert_eq!(log[1], Some(0.25));
+    assert_eq!(log[3], Some(1.0));
+
+    // equal shares, whatever the spacing
+    let histogram = Mapping::Histogram.intensities(&grid, 15);
+    assert_eq!(
+        histogram,
+        [Some(0.25), Some(0.5), Some(0.75), Some(1.0), None]
+    );
+
+    let modulo = Mapping::Modulo(4).intensities(&grid, 15);
+    assert_eq!(
+        modulo,
+        [Some(0.0), Some(0.25), Some(0.75), Some(0.75), None]
+    );
+}
+
+#[test]
+fn mapping_names() {
+    for mapping in Mapping::ALL {
+        assert_eq!(Mapping::from_name(mapping.name()), Some(mapping));
+    }
+    assert_eq!(Mapping::from_name("modulo:8"), Some(Mapping::Modulo(8)));
+    assert_eq!(Mapping::from_name("modulo:0"), None);
+    assert_eq!(Mapping::from_name("sqrt"), None);
+}
+
+#[test]
+fn ramps() {
+    let shades = [None, Some(1.0), Some(0.55), Some(0.0)];
+    let draw = |ramp: &Ramp| -> String { shades.iter().map(|&t| ramp.shade_char(t)).collect() };
+
+    assert_eq!(draw(&Ramp::named("digits").unwrap()), "0049");
+    assert_eq!(draw(&Ramp::named("blocks").unwrap()), "██▒ ");
+    assert_eq!(draw(&Ramp::default().inverted()), "  =@");
+    assert_eq!(draw(&Ramp::new("#-").unwrap()), "###-");
+    assert_eq!(Ramp::named("long").unwrap().value_char(255), ' ');
+    assert_eq!(Ramp::named("long").unwrap().value_char(0), '$');
+    for name in Ramp::NAMES {
+        assert!(Ramp::named(name).is_some(), "{}", name);
+    }
+    assert_eq!(Ramp::new("#"), None);
+}
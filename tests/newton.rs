@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Tests for the newton fractal.
+
+use float_test::newton::{self, Newton};
+use float_test::{Precision, MAX_ITER};
+use num::complex::Complex;
+
+#[test]
+fn cubic_roots_of_unity() {
+    let cubic = Newton::cubic(MAX_ITER);
+
+    assert_eq!(cubic.roots().len(), 3);
+    for root in cubic.roots() {
+        assert!((root.powi(3) - 1.0).norm() < 1e-12, "{} isn't a root", root);
+        assert!((root.norm() - 1.0).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn points_converge_to_the_nearest_root() {
+    let cubic = Newton::cubic(MAX_ITER);
+
+    for (i, &root) in cubic.roots().iter().enumerate() {
+        let start = root.scale(1.1);
+        assert_eq!(cubic.iter(start).map(|(r, _)| r), Some(i));
+        assert_eq!(
+            cubic
+                .iter(Complex::new(start.re as f32, start.im as f32))
+                .map(|(r, _)| r),
+            Some(i)
+        );
+    }
+    // the derivative vanishes at 0, so the first step divides by zero
+    assert_eq!(cubic.iter(Complex::new(0.0f64, 0.0)), None);
+}
+
+#[test]
+fn polynomials() {
+    let quartic = Newton::new(MAX_ITER, &[0.0, 2.0, 0.0, -3.0, 0.0, 1.0]).unwrap();
+
+    assert_eq!(quartic.to_string(), "2z^4 - 3z^2 + 1");
+    assert_eq!(quartic.roots().len(), 4);
+    assert_eq!(Newton::cubic(MAX_ITER).to_string(), "z^3 - 1");
+    assert!(Newton::new(MAX_ITER, &[3.0, 1.0]).is_none());
+    assert!(Newton::new(MAX_ITER, &[0.0, 0.0]).is_none());
+    assert!(Newton::new(MAX_ITER, &[1.0, f64::NAN, 1.0]).is_none());
+}
+
+#[test]
+fn precisions_mostly_agree() {
+    let cubic = Newton::cubic(64);
+    let view = newton::VIEW;
+    let single = newton::render(Precision::Single, &cubic, view.min, view.max, 32, 32, 1);
+    let double = newton::render(Precision::Double, &cubic, view.min, view.max, 32, 32, 1);
+    let same_root = single
+        .iter()
+        .zip(&double)
+        .filter(|(s, d)| s.map(|(r, _)| r) == d.map(|(r, _)| r))
+        .count();
+
+    // only points right on the basin boundaries can go either way
+    assert!(same_root > 32 * 32 * 9 / 10, "{} of 1024 agree", same_root);
+}
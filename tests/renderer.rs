@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Tests for the multi-sample text renderers.
+
+use float_test::{escapes, ColorMode, Colors, Intensity, Iter, Mapping, Palette, Ramp, Renderer};
+
+// intensities for iteration values as from Ifs::iter, with 0 inside the set
+fn shades(values: &[Iter]) -> Vec<Intensity> {
+    Mapping::Linear.intensities(&escapes(values, 16), 16)
+}
+
+#[test]
+fn halfblock_plain() {
+    // top row, then bottom row; 0 is inside the set
+    let grid = shades(&[0, 0, 9, 9, 0, 9, 0, 9]);
+    let lines = Renderer::HalfBlock.render(&grid, 4, &Colors::PLAIN, &Ramp::default());
+
+    assert_eq!(lines, ["█▀▄ "]);
+}
+
+#[test]
+fn braille_dots() {
+    // a 4x4 grid: left cell fully inside, right cell only the top-left dot
+    let grid = shades(&[
+        0, 0, 0, 9, //
+        0, 0, 9, 9, //
+        0, 0, 9, 9, //
+        0, 0, 9, 9, //
+    ]);
+    let lines = Renderer::Braille.render(&grid, 4, &Colors::PLAIN, &Ramp::default());
+
+    assert_eq!(lines, ["⣿⠁"]);
+}
+
+#[test]
+fn coloured_halfblock_resets() {
+    let colors = Colors {
+        mode: ColorMode::TrueColor,
+        palette: Palette::Grey,
+    };
+    let lines = Renderer::HalfBlock.render(&shades(&[1, 0, 2, 0]), 2, &colors, &Ramp::default());
+
+    assert_eq!(lines[0].matches('▀').count(), 2);
+    assert!(lines[0].ends_with("\x1b[0m"));
+}
+
+#[test]
+fn samples_per_cell() {
+    for renderer in Renderer::ALL {
+        let (x, y) = renderer.samples();
+        assert!(x >= 1 && y >= 1);
+        assert_eq!(Renderer::from_name(renderer.name()), Some(renderer));
+    }
+}
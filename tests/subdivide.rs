@@ -0,0 +1,142 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+//
+// Copyright 2022 Andrew Powers-Holmes <aholmes@omnom.net>
+//
+// Tests of the subdivision renderer against brute force.
+
+use float_test::subdivide::{self, subdivide_grid};
+use float_test::{reference, render, render_smooth, Ifs, Precision, Viewport};
+use float_test::{MAX_ITER, VIEW_MAX, VIEW_MIN};
+use num::complex::Complex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn reference_grid_matches() {
+    for precision in Precision::ALL {
+        let mandel = Ifs::new(MAX_ITER);
+        let grid = subdivide::render(
+            precision,
+            &mandel,
+            VIEW_MIN,
+            VIEW_MAX,
+            reference::COLS,
+            reference::ROWS,
+            1,
+        );
+        assert_eq!(grid, reference::render(precision), "{}", precision);
+    }
+}
+
+#[test]
+fn matches_brute_force() {
+    let cases = [
+        (Ifs::new(MAX_ITER), Viewport::default()),
+        (Ifs::new(1000), Viewport::default()),
+        (
+            Ifs::new(MAX_ITER),
+            Viewport::centered(Complex::new(-1.25, 0.02), 20.0),
+        ),
+        (
+            Ifs::new(500),
+            Viewport::centered(Complex::new(-0.745, 0.11), 50.0),
+        ),
+    ];
+    for precision in Precision::ALL {
+        for (mandel, view) in &cases {
+            let brute = render(precision, mandel, view.min, view.max, 200, 100, 1);
+            for threads in [1, 3] {
+                let grid =
+                    subdivide::render(precision, mandel, view.min, view.max, 200, 100, threads);
+                assert_eq!(grid, brute, "{} {:?} {} threads", precision, view, threads);
+            }
+
+            let brute = render_smooth(precision, mandel, view.min, view.max, 80, 40, 1);
+            let grid = subdivide::render_smooth(precision, mandel, view.min, view.max, 80, 40, 1);
+            assert_eq!(grid, brute, "{} {:?} smooth", precision, view);
+        }
+    }
+}
+
+#[test]
+fn zoomed_out() {
+    // the whole set fits well inside a border of escaping points
+    let mandel = Ifs::new(MAX_ITER);
+    for size in [5.0, 8.0, 10.0, 20.0, 40.0] {
+        let (min, max) = (Complex::new(-size, -size), Complex::new(size, size));
+        for precision in Precision::ALL {
+            let brute = render(precision, &mandel, min, max, 80, 40, 1);
+            let grid = subdivide::render(precision, &mandel, min, max, 80, 40, 1);
+            assert_eq!(grid, brute, "{} +/-{}", precision, size);
+        }
+    }
+}
+
+#[test]
+fn skips_uniform_regions() {
+    let mandel = Ifs::new(MAX_ITER);
+    let evaluated = AtomicUsize::new(0);
+    let grid = subdivide_grid::<f64, _>(
+        VIEW_MIN,
+        VIEW_MAX,
+        200,
+        100,
+        1,
+        |c| {
+            evaluated.fetch_add(1, Ordering::Relaxed);
+            mandel.iter(c)
+        },
+        |value| value == 0,
+    );
+
+    assert_eq!(grid.len(), 200 * 100);
+    // most of the set's interior is filled in
+    let inside = grid.iter().filter(|&&v| v == 0).count();
+    assert!(evaluated.into_inner() < grid.len() - inside / 2);
+}
+
+#[test]
+fn odd_sizes() {
+    let mandel = Ifs::new(MAX_ITER);
+    for (cols, rows) in [(1, 1), (1, 9), (9, 1), (5, 5), (17, 3), (0, 4)] {
+        let brute = render(
+            Precision::Double,
+            &mandel,
+            VIEW_MIN,
+            VIEW_MAX,
+            cols,
+            rows,
+            1,
+        );
+        for threads in [1, 4] {
+            let grid = subdivide::render(
+                Precision::Double,
+                &mandel,
+                VIEW_MIN,
+                VIEW_MAX,
+                cols,
+                rows,
+                threads,
+            );
+            assert_eq!(grid, brute, "{}x{} {} threads", cols, rows, threads);
+        }
+    }
+}
+
+#[test]
+fn same_for_any_thread_count() {
+    let mandel = Ifs::new(500);
+    let view = Viewport::centered(Complex::new(-0.745, 0.11), 50.0);
+    let single = subdivide::render(Precision::Double, &mandel, view.min, view.max, 256, 256, 1);
+    for threads in [2, 3, 8] {
+        let grid = subdivide::render(
+            Precision::Double,
+            &mandel,
+            view.min,
+            view.max,
+            256,
+            256,
+            threads,
+        );
+        assert_eq!(grid, single, "{} threads", threads);
+    }
+}